@@ -1,6 +1,26 @@
-use crate::api::{HackerNewsClient, Story, StoryType};
+use crate::api::{self, ApiError, HackerNewsClient, Story, StoryType, User};
+use crate::cache::StoryCache;
+use crate::command::Command;
+use crate::comments::{self, CommentNode};
+use crate::config::{Config, ListColumns};
+use crate::fuzzy::{self, Match};
+use crate::html;
+use crate::storage;
+use crate::theme::Theme;
+use anyhow::Result;
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 pub enum AppState {
     Loading,
@@ -9,6 +29,251 @@ pub enum AppState {
     Error(String),
 }
 
+pub const DEFAULT_BATCH_SIZE: usize = 30;
+/// Number of stories assumed to fit on screen until the first frame is drawn.
+const DEFAULT_VIEWPORT_HEIGHT: usize = 20;
+const DEFAULT_AUTO_LOAD_THRESHOLD: usize = 5;
+const DEFAULT_SPLIT_RATIO: u16 = 60;
+/// How much `+`/`-` change the minimum score filter by.
+const MIN_SCORE_STEP: i64 = 10;
+
+/// How long the selection has to rest on a story before its comments are prefetched.
+const PREFETCH_DELAY: Duration = Duration::from_millis(500);
+
+/// How long a first `q` waits for the second one when `confirm_quit` is on.
+const QUIT_CONFIRM_WINDOW: Duration = Duration::from_secs(2);
+/// How long a flashed status message, such as a command error, stays up.
+const FLASH_DURATION: Duration = Duration::from_secs(3);
+/// How often `/updates.json` is polled while watching for updates.
+const UPDATES_INTERVAL: Duration = Duration::from_secs(30);
+/// How long id lists from the startup warm-up stay fresh enough to show a category from.
+const WARM_IDS_MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How the loaded stories are ordered. Sorting only reorders what has been loaded so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortMode {
+    /// HN's own ranking for the category.
+    Default,
+    Score,
+    Comments,
+    /// Newest first.
+    Time,
+}
+
+impl SortMode {
+    fn next(self) -> Self {
+        match self {
+            SortMode::Default => SortMode::Score,
+            SortMode::Score => SortMode::Comments,
+            SortMode::Comments => SortMode::Time,
+            SortMode::Time => SortMode::Default,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            SortMode::Default => "rank",
+            SortMode::Score => "score",
+            SortMode::Comments => "comments",
+            SortMode::Time => "time",
+        }
+    }
+}
+
+impl FromStr for SortMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "rank" | "default" => Ok(SortMode::Default),
+            "score" => Ok(SortMode::Score),
+            "comments" => Ok(SortMode::Comments),
+            "time" => Ok(SortMode::Time),
+            other => Err(format!("unknown sort: {}", other)),
+        }
+    }
+}
+
+/// Whether details replace the story list or sit beside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutMode {
+    /// The list fills the screen and `d` swaps it for the details.
+    Single,
+    /// The list on the left, with the selected story's details on the right.
+    Split,
+}
+
+/// File format for `e` exports of the loaded stories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Markdown => "md",
+        }
+    }
+}
+
+/// One story as written by a JSON export.
+#[derive(Serialize)]
+struct ExportedStory<'a> {
+    title: String,
+    url: Option<&'a str>,
+    score: i64,
+    hn_url: String,
+}
+
+/// How a story is styled because of the domain it links to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomainMark {
+    Highlight,
+    Dim,
+}
+
+/// The author profile shown over the details view.
+pub enum UserView {
+    Loading(String),
+    Loaded(User),
+    Error(String),
+}
+
+/// How long the network took for the latest batch, shown in debug mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadTiming {
+    /// Fetching the category's id list. `None` when the batch didn't need one, as when
+    /// loading more or showing bookmarks.
+    pub ids: Option<Duration>,
+    /// Fetching the batch's items, all together.
+    pub items: Duration,
+    pub count: usize,
+}
+
+impl LoadTiming {
+    /// E.g. "ids 120ms, 30 items 850ms (28ms/item)".
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        if let Some(ids) = self.ids {
+            summary.push_str(&format!("ids {}ms, ", ids.as_millis()));
+        }
+        summary.push_str(&format!("{} items {}ms", self.count, self.items.as_millis()));
+        if self.count > 0 {
+            let average = self.items / self.count as u32;
+            summary.push_str(&format!(" ({}ms/item)", average.as_millis()));
+        }
+        summary
+    }
+}
+
+/// A story's discussion shown as a thread of comments.
+pub enum CommentsView {
+    /// The comments of this story are being fetched.
+    Loading(i64),
+    Loaded(CommentThread),
+    Error(String),
+}
+
+pub struct CommentThread {
+    pub nodes: Vec<CommentNode>,
+    /// Position of the selected comment among those on screen.
+    pub selected: usize,
+}
+
+/// A linked article shown as text in the terminal instead of in the browser.
+pub enum ReaderView {
+    /// The page at this URL is being fetched.
+    Loading(String),
+    Loaded(String),
+    Error(String),
+}
+
+/// A search of all of HN through Algolia, loaded a page at a time.
+#[derive(Debug)]
+pub struct RemoteSearch {
+    pub query: String,
+    /// Pages loaded so far; the next "load more" fetches this one.
+    pages: usize,
+    has_more: bool,
+}
+
+/// The list that was on screen before switching categories, put back if the switch is
+/// cancelled before the new category has anything to show.
+struct PreviousList {
+    story_type: StoryType,
+    stories: Vec<Story>,
+    all_story_ids: Vec<i64>,
+    loaded_count: usize,
+    selected_index: usize,
+    scroll_offset: usize,
+    single_item: Option<i64>,
+    remote_search: Option<RemoteSearch>,
+}
+
+/// Progress of a background load, sent from the spawned task back to the event loop.
+pub enum LoadResult {
+    /// The category's id list arrived; the first `end` of them are being fetched.
+    Ids {
+        generation: u64,
+        ids: Vec<i64>,
+        end: usize,
+    },
+    /// One story from the current batch resolved.
+    Story { generation: u64, story: Story },
+    /// The current batch finished; stories up to `end` in the id list have been tried,
+    /// and `failures` of them couldn't be loaded.
+    Done {
+        generation: u64,
+        end: usize,
+        failures: usize,
+    },
+    Failed {
+        generation: u64,
+        error: anyhow::Error,
+    },
+    /// How long the batch's requests took, sent just before its `Done`.
+    Timing {
+        generation: u64,
+        timing: LoadTiming,
+    },
+    User {
+        username: String,
+        result: Result<User>,
+    },
+    /// The comment tree of `story_id`, for the comment view.
+    Comments {
+        story_id: i64,
+        nodes: Vec<CommentNode>,
+    },
+    /// The article at `url` was fetched and extracted for the reader.
+    Article {
+        url: String,
+        result: Result<String>,
+    },
+    /// A category's id list from the startup warm-up.
+    WarmIds { story_type: StoryType, ids: Vec<i64> },
+    /// A page of full-text search results arrived; `ids` are the stories not already
+    /// listed, which are fetched next.
+    SearchPage {
+        generation: u64,
+        ids: Vec<i64>,
+        has_more: bool,
+    },
+    /// The first comment of `story_id` resolved, for the list preview.
+    TopComment { story_id: i64, text: String },
+    /// The options of a poll, in the poll's order.
+    PollOptions { poll_id: i64, options: Vec<Story> },
+    /// A top-level comment prefetched for the selected story.
+    Comment(Story),
+    /// Ids of the items HN reports as recently changed.
+    Updates(Vec<i64>),
+}
+
 pub struct App {
     pub stories: Vec<Story>,
     pub selected_index: usize,
@@ -16,175 +281,3011 @@ pub struct App {
     pub state: AppState,
     pub error_message: Option<String>,
     pub scroll_offset: usize,
+    /// Rows available to the story list, updated from the content area every frame.
+    pub viewport_height: usize,
     pub show_details: bool,
-    pub client: Arc<Mutex<HackerNewsClient>>,
+    /// Lines the details view is scrolled down by. Back to 0 for each new story.
+    pub details_scroll: u16,
+    /// Furthest `details_scroll` can go, updated from the details area every frame.
+    details_max_scroll: u16,
+    /// The story `details_scroll` applies to.
+    details_scroll_id: Option<i64>,
+    pub layout_mode: LayoutMode,
+    /// Percentage of the width given to the story list in the split layout.
+    pub split_ratio: u16,
+    /// Show absolute local timestamps instead of "3h ago" in the details view.
+    pub show_absolute_time: bool,
+    /// chrono format for absolute timestamps, from the config.
+    pub time_format: String,
+    /// Discussion page URL with an `{id}` placeholder, from the config.
+    pub item_url_template: String,
+    /// Shared by every background load. It limits and deduplicates requests itself, so
+    /// loads run concurrently.
+    pub client: Arc<HackerNewsClient>,
+    /// Host of the proxy requests go through, if any, for error messages.
+    pub proxy: Option<String>,
+    /// Serve stories from `cache` only and never touch the network.
+    pub offline: bool,
+    pub cache: StoryCache,
     pub all_story_ids: Vec<i64>,
     pub loaded_count: usize,
     pub batch_size: usize,
+    /// Batch sizes for categories that don't use `batch_size`, from the config.
+    pub category_batch_sizes: HashMap<StoryType, usize>,
+    /// Size of the first batch of each category, from `--limit`, overriding the
+    /// category's batch size.
+    pub first_batch_size: Option<usize>,
+    /// Load more stories automatically when the selection gets this close to the end
+    /// of the list. 0 disables it.
+    pub auto_load_threshold: usize,
+    pub search_query: Option<String>,
+    pub searching: bool,
+    /// Only stories from this domain are shown, until cleared with Esc.
+    pub domain_filter: Option<String>,
+    /// Digits typed in the list so far, for jumping to a rank with Enter.
+    pub number_buffer: String,
+    /// The command being typed after `:`, while the command prompt is open.
+    pub command_buffer: Option<String>,
+    pub show_help: bool,
+    /// Show a one-line preview of each visible story's first comment.
+    pub show_comment_previews: bool,
+    /// Stories whose first comment has already been requested, so it is fetched only once.
+    preview_requests: HashSet<i64>,
+    /// Options of polls shown in the details view, keyed by poll id.
+    pub poll_options: HashMap<i64, Vec<Story>>,
+    /// Polls whose options have already been requested.
+    poll_requests: HashSet<i64>,
+    /// The selected story and when it was selected, for debouncing comment prefetches.
+    selection_since: Option<(i64, Instant)>,
+    /// Stories whose top-level comments have already been prefetched.
+    prefetched: HashSet<i64>,
+    /// The prefetched comments by id, for `open_comments` to start from.
+    prefetched_comments: HashMap<i64, Story>,
+    /// Id lists of the other categories, fetched in the background once the first one
+    /// has loaded, so switching to one only has to fetch its stories. Each is used once.
+    id_cache: HashMap<StoryType, Vec<i64>>,
+    /// When the warm-up filling `id_cache` started; `None` until it has.
+    ids_warmed_at: Option<Instant>,
+    pub user_view: Option<UserView>,
+    pub reader: Option<ReaderView>,
+    pub comments_view: Option<CommentsView>,
+    /// Lines scrolled past at the top of the reader.
+    pub reader_scroll: u16,
+    /// Furthest `reader_scroll` can go, updated from the reader area every frame.
+    reader_max_scroll: u16,
+    pub sort_mode: SortMode,
+    /// Stories scoring below this are hidden. 0 shows everything.
+    pub min_score: i64,
+    /// Hide stories that have been opened instead of just dimming them.
+    pub hide_read: bool,
+    /// Leave items without a title out of the list, from the config.
+    pub hide_untitled: bool,
+    /// Domains whose stories are emphasized or muted in the list, from the config.
+    pub highlight_domains: Vec<String>,
+    pub dim_domains: Vec<String>,
+    /// Fields shown after each title in the list, from the config.
+    pub list_columns: ListColumns,
+    /// Widest a title may be in the list before it is cut off, from the config. `None`
+    /// wraps long titles instead.
+    pub max_title_len: Option<usize>,
+    pub bookmarks: Vec<i64>,
+    /// Stories set aside to read next, oldest first. Unlike bookmarks, emptied as they
+    /// are opened.
+    pub queue: Vec<i64>,
+    /// Save `queue` between sessions, from the config.
+    persist_queue: bool,
+    /// Whether bookmarks, read stories and the other saved state are written to disk.
+    persist: bool,
+    /// Ids of stories opened in the browser, shared across all categories.
+    pub read: HashSet<i64>,
+    /// Comment count of each story when its discussion was last opened, so the list can
+    /// show how many comments are new since.
+    pub comment_snapshot: HashMap<i64, i64>,
+    pub status_message: Option<String>,
+    /// When a flashed `status_message` should disappear on its own.
+    status_expires: Option<Instant>,
+    pub export_format: ExportFormat,
+    /// Require a second `q` to quit, from the config.
+    pub confirm_quit: bool,
+    /// `j` on the last story goes to the first and `k` on the first to the last, from
+    /// the config.
+    pub wrap_navigation: bool,
+    /// When the first `q` was pressed, while waiting for the confirming one.
+    pub pending_quit: Option<Instant>,
+    /// How often the New feed refreshes itself while shown, from the config.
+    pub auto_refresh: Option<Duration>,
+    /// When the current category was last (re)loaded, for timing the next auto-refresh.
+    last_refresh: Instant,
+    /// Poll HN's changed-items feed and mark loaded stories that appear in it.
+    pub watch_updates: bool,
+    /// Ids from the latest changed-items poll.
+    pub updated_ids: HashSet<i64>,
+    /// When the changed-items feed was last requested.
+    updates_polled: Option<Instant>,
+    /// When a feed was last fetched successfully from the network.
+    pub last_loaded: Option<DateTime<Utc>>,
+    pub spinner_frame: usize,
+    pub theme: Theme,
+    /// Number of items resolved so far in the in-flight batch, out of `load_total`.
+    pub load_progress: Arc<AtomicUsize>,
+    pub load_total: usize,
+    /// Items in the latest batch that couldn't be loaded, even after retrying.
+    pub last_load_failures: usize,
+    /// Show request timings in the status bar, set by `--debug`.
+    pub debug: bool,
+    pub last_timing: Option<LoadTiming>,
+    /// Score and comment count of every story as of the last load that included it.
+    pub previous_snapshot: HashMap<i64, (i64, i64)>,
+    /// How much each story's score and comment count changed in the latest refresh.
+    score_deltas: HashMap<i64, (i64, i64)>,
+    /// Selection and scroll offset remembered for each category when switching away.
+    pub positions: HashMap<StoryType, (usize, usize)>,
+    /// Set while showing a single item opened with `--item` instead of a category.
+    pub single_item: Option<i64>,
+    /// Set while showing full-text search results instead of a category.
+    pub remote_search: Option<RemoteSearch>,
+    /// Story to re-select once a refresh finishes, so the selection follows it to its new rank.
+    reselect_id: Option<i64>,
+    /// Set from `start_list` until the load finishes. Until then the selection is only a
+    /// remembered position in a list still streaming in, not a story to follow.
+    fresh_list: bool,
+    /// The category `stories` were loaded for. Trails `story_type` between switching
+    /// categories and loading the new one.
+    list_type: StoryType,
+    /// Kept while a category switch is loading; see `PreviousList`.
+    previous_list: Option<PreviousList>,
+    /// The category or batch fetch in progress, kept so `cancel_load` can stop it.
+    load_task: Option<JoinHandle<()>>,
+    load_generation: u64,
+    load_tx: mpsc::UnboundedSender<LoadResult>,
+    load_rx: mpsc::UnboundedReceiver<LoadResult>,
 }
 
 impl App {
     pub fn new() -> Self {
+        Self::new_with_batch_size(DEFAULT_BATCH_SIZE)
+    }
+
+    pub fn new_with_batch_size(batch_size: usize) -> Self {
+        let (config, config_error) = Config::load();
+        Self::with_config(config, config_error, batch_size, true)
+    }
+
+    /// Like `new_with_batch_size`, with the API at `api_url` instead of the configured
+    /// one, e.g. from `--api-url`. An invalid `api_url` in the config goes unreported,
+    /// since it isn't used.
+    pub fn with_api_url(batch_size: usize, api_url: &str) -> Self {
+        let (mut config, config_error) = Config::load();
+        config.api_url = Some(api_url.to_string());
+        Self::with_config(config, config_error, batch_size, true)
+    }
+
+    /// An app with the default config that neither reads nor writes anything on disk.
+    #[cfg(test)]
+    pub fn for_tests() -> Self {
+        Self::with_config(Config::default(), None, DEFAULT_BATCH_SIZE, false)
+    }
+
+    /// Builds the app from `config`. With `persist` off, bookmarks, the story cache and
+    /// the rest of the saved state start out empty and are never saved.
+    fn with_config(config: Config, config_error: Option<String>, batch_size: usize, persist: bool) -> Self {
+        let (load_tx, load_rx) = mpsc::unbounded_channel();
+        let mut status_message = None;
+        let client = match config.proxy.as_deref() {
+            Some(url) => HackerNewsClient::with_proxy(url).unwrap_or_else(|e| {
+                status_message = Some(format!("Ignoring invalid proxy {}: {}", api::proxy_host(url), e));
+                HackerNewsClient::new()
+            }),
+            None => HackerNewsClient::new(),
+        };
+        let proxy = match status_message {
+            None => config.proxy.clone().or_else(api::env_proxy),
+            Some(_) => api::env_proxy(),
+        }
+        .map(|url| api::proxy_host(&url));
+        if let Some(e) = config_error {
+            status_message.get_or_insert(e);
+        }
+        let time_format = config.time_format().unwrap_or_else(|e| {
+            status_message.get_or_insert(e);
+            api::DEFAULT_TIME_FORMAT.to_string()
+        });
+        let api_url = config.api_url().unwrap_or_else(|e| {
+            status_message.get_or_insert(e);
+            api::HN_API_BASE.to_string()
+        });
+        let theme = config.theme().unwrap_or_else(|e| {
+            status_message.get_or_insert(e);
+            Theme::default()
+        });
+        let item_url_template = config.item_url_template().unwrap_or_else(|e| {
+            status_message.get_or_insert(e);
+            api::DEFAULT_ITEM_URL_TEMPLATE.to_string()
+        });
+        let client = client
+            .with_base_url(&api_url)
+            .with_max_concurrent_requests(
+                config
+                    .max_concurrent_requests
+                    .unwrap_or(api::DEFAULT_MAX_CONCURRENT_REQUESTS),
+            )
+            .with_chunk_size(config.fetch_chunk_size.unwrap_or(api::DEFAULT_CHUNK_SIZE));
         Self {
             stories: Vec::new(),
             selected_index: 0,
-            story_type: StoryType::Top,
+            story_type: config.default_story_type(),
             state: AppState::Loading,
             error_message: None,
             scroll_offset: 0,
+            viewport_height: DEFAULT_VIEWPORT_HEIGHT,
             show_details: false,
-            client: Arc::new(Mutex::new(HackerNewsClient::new())),
+            details_scroll: 0,
+            details_max_scroll: 0,
+            details_scroll_id: None,
+            layout_mode: LayoutMode::Single,
+            split_ratio: config
+                .split_ratio
+                .unwrap_or(DEFAULT_SPLIT_RATIO)
+                .clamp(20, 80),
+            show_absolute_time: false,
+            time_format,
+            item_url_template,
+            client: Arc::new(client),
+            proxy,
+            offline: false,
+            cache: if persist { storage::load_story_cache() } else { StoryCache::default() },
             all_story_ids: Vec::new(),
             loaded_count: 0,
-            batch_size: 30,
+            batch_size,
+            category_batch_sizes: config.category_batch_sizes(),
+            first_batch_size: None,
+            auto_load_threshold: config
+                .auto_load_threshold
+                .unwrap_or(DEFAULT_AUTO_LOAD_THRESHOLD),
+            search_query: None,
+            domain_filter: None,
+            searching: false,
+            number_buffer: String::new(),
+            command_buffer: None,
+            show_help: false,
+            show_comment_previews: false,
+            preview_requests: HashSet::new(),
+            poll_options: HashMap::new(),
+            poll_requests: HashSet::new(),
+            selection_since: None,
+            prefetched: HashSet::new(),
+            prefetched_comments: HashMap::new(),
+            id_cache: HashMap::new(),
+            ids_warmed_at: None,
+            user_view: None,
+            reader: None,
+            comments_view: None,
+            reader_scroll: 0,
+            reader_max_scroll: 0,
+            sort_mode: SortMode::Default,
+            min_score: 0,
+            hide_read: false,
+            hide_untitled: config.hide_untitled,
+            highlight_domains: config.highlight_domains.clone(),
+            dim_domains: config.dim_domains.clone(),
+            list_columns: config.list_columns(),
+            max_title_len: config.max_title_len.filter(|&len| len > 0),
+            bookmarks: persist.then(storage::load_bookmarks).unwrap_or_default(),
+            queue: if config.persist_queue && persist {
+                storage::load_queue()
+            } else {
+                Vec::new()
+            },
+            persist_queue: config.persist_queue,
+            persist,
+            read: persist.then(storage::load_read_stories).unwrap_or_default(),
+            comment_snapshot: persist.then(storage::load_comment_snapshot).unwrap_or_default(),
+            status_message,
+            status_expires: None,
+            export_format: config.export_format(),
+            confirm_quit: config.confirm_quit,
+            wrap_navigation: config.wrap_navigation,
+            pending_quit: None,
+            auto_refresh: config
+                .auto_refresh_secs
+                .filter(|&secs| secs > 0)
+                .map(Duration::from_secs),
+            last_refresh: Instant::now(),
+            watch_updates: false,
+            updated_ids: HashSet::new(),
+            updates_polled: None,
+            last_loaded: None,
+            spinner_frame: 0,
+            theme: if persist { Theme::load(theme) } else { theme },
+            load_progress: Arc::new(AtomicUsize::new(0)),
+            load_total: 0,
+            last_load_failures: 0,
+            debug: false,
+            last_timing: None,
+            previous_snapshot: HashMap::new(),
+            score_deltas: HashMap::new(),
+            positions: HashMap::new(),
+            single_item: None,
+            remote_search: None,
+            reselect_id: None,
+            fresh_list: false,
+            list_type: config.default_story_type(),
+            previous_list: None,
+            load_task: None,
+            load_generation: 0,
+            load_tx,
+            load_rx,
         }
     }
 
-    pub async fn load_stories(&mut self) {
-        self.state = AppState::Loading;
+    /// Starts loading the first batch of the current category in the background.
+    /// Cached stories are shown right away; fresh ones stream in through `poll_loads`
+    /// as each one resolves, replacing them.
+    pub fn load_stories(&mut self) {
+        self.load_stories_up_to(self.first_batch_len());
+    }
+
+    /// Like `load_stories`, with a first batch of `len` stories instead of the usual.
+    fn load_stories_up_to(&mut self, len: usize) {
+        let previous = (self.list_type != self.story_type && !self.stories.is_empty()).then(|| {
+            PreviousList {
+                story_type: self.list_type,
+                stories: std::mem::take(&mut self.stories),
+                all_story_ids: std::mem::take(&mut self.all_story_ids),
+                loaded_count: self.loaded_count,
+                selected_index: self.selected_index,
+                scroll_offset: self.scroll_offset,
+                single_item: self.single_item,
+                remote_search: self.remote_search.take(),
+            }
+        });
+        self.list_type = self.story_type;
         self.error_message = None;
+        self.load_generation += 1;
+        self.reselect_id = None;
+        self.single_item = None;
+        self.remote_search = None;
+        self.score_deltas.clear();
+        self.last_refresh = Instant::now();
 
-        let client = self.client.lock().await;
-        match client.get_story_ids(self.story_type).await {
-            Ok(ids) => {
-                self.all_story_ids = ids;
-                self.loaded_count = 0;
-                let new_stories = client
-                    .get_stories_by_ids(&self.all_story_ids[self.loaded_count..self.loaded_count.saturating_add(self.batch_size).min(self.all_story_ids.len())])
-                    .await;
-                match new_stories {
-                    Ok(mut stories) => {
-                        self.stories.append(&mut stories);
-                        self.loaded_count = self.stories.len();
-                        self.selected_index = 0;
-                        self.scroll_offset = 0;
-                        self.state = AppState::Ready;
-                    }
-                    Err(e) => {
-                        self.error_message = Some(e.to_string());
-                        self.state = AppState::Error(e.to_string());
+        self.load_cached_stories(len);
+        if self.offline {
+            return;
+        }
+        self.previous_list = previous;
+        self.state = if self.stories.is_empty() {
+            AppState::Loading
+        } else {
+            AppState::LoadingMore
+        };
+
+        let generation = self.load_generation;
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        let story_type = self.story_type;
+        let bookmarks = self.bookmarks.clone();
+        let batch_size = len;
+        let progress = self.reset_progress(0);
+        let warm_ids = self.take_warm_ids(story_type);
+
+        self.spawn_load(async move {
+            let started = Instant::now();
+            let mut ids_elapsed = None;
+            let ids = match (story_type, warm_ids) {
+                (StoryType::Saved, _) => bookmarks,
+                (_, Some(ids)) => ids,
+                (story_type, None) => {
+                    let ids = match story_type {
+                        StoryType::All => client.get_all_category_ids().await,
+                        story_type => client.get_story_ids(story_type).await,
+                    };
+                    ids_elapsed = Some(started.elapsed());
+                    match ids {
+                        Ok(ids) => ids,
+                        Err(error) => {
+                            let _ = tx.send(LoadResult::Failed { generation, error });
+                            return;
+                        }
                     }
                 }
+            };
+            let end = batch_size.min(ids.len());
+            let _ = tx.send(LoadResult::Ids {
+                generation,
+                ids: ids.clone(),
+                end,
+            });
+            let started = Instant::now();
+            let failures = stream_batch(&client, &ids[..end], &progress, generation, &tx).await;
+            let timing = LoadTiming {
+                ids: ids_elapsed,
+                items: started.elapsed(),
+                count: end,
+            };
+            let _ = tx.send(LoadResult::Timing { generation, timing });
+            let _ = tx.send(LoadResult::Done {
+                generation,
+                end,
+                failures,
+            });
+        });
+    }
+
+    /// Shows just the item `id`, fetched on its own rather than through a category.
+    /// Offline, it can only come from the cache.
+    pub fn load_single_item(&mut self, id: i64) {
+        self.state = AppState::Loading;
+        self.error_message = None;
+        self.load_generation += 1;
+        self.reselect_id = None;
+        self.single_item = Some(id);
+        self.remote_search = None;
+        self.start_list(vec![id], 1);
+
+        let generation = self.load_generation;
+        if self.offline {
+            match self.cache.get(id).cloned() {
+                Some(story) => {
+                    self.apply_load_result(LoadResult::Story { generation, story });
+                    self.apply_load_result(LoadResult::Done {
+                        generation,
+                        end: 1,
+                        failures: 0,
+                    });
+                }
+                None => self.apply_load_result(LoadResult::Failed {
+                    generation,
+                    error: anyhow::anyhow!("Item {} is not cached", id),
+                }),
             }
-            Err(e) => {
-                self.error_message = Some(e.to_string());
-                self.state = AppState::Error(e.to_string());
+            return;
+        }
+
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        self.spawn_load(async move {
+            match client.get_story(id).await {
+                Ok(story) => {
+                    let _ = tx.send(LoadResult::Story { generation, story });
+                    let _ = tx.send(LoadResult::Done {
+                        generation,
+                        end: 1,
+                        failures: 0,
+                    });
+                }
+                Err(error) => {
+                    let _ = tx.send(LoadResult::Failed { generation, error });
+                }
             }
+        });
+    }
+
+    /// Shows the stories matching `query` from all of HN, not just the loaded ones.
+    /// Needs a connection, since Algolia does the searching.
+    pub fn search_hn(&mut self, query: String) {
+        if self.offline {
+            self.flash("Searching HN needs a connection".to_string());
+            return;
+        }
+        if self.showing_category() {
+            self.positions
+                .insert(self.story_type, (self.selected_index, self.scroll_offset));
+        }
+        self.state = AppState::Loading;
+        self.error_message = None;
+        self.load_generation += 1;
+        self.reselect_id = None;
+        self.single_item = None;
+        self.remote_search = Some(RemoteSearch {
+            query,
+            pages: 0,
+            has_more: false,
+        });
+        self.start_list(Vec::new(), 0);
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+        self.load_search_page();
+    }
+
+    /// Fetches the next page of the current search in the background.
+    fn load_search_page(&mut self) {
+        let Some(search) = &self.remote_search else {
+            return;
+        };
+        let query = search.query.clone();
+        let page = search.pages;
+        let generation = self.load_generation;
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        let mut known = self.all_story_ids.clone();
+        let progress = self.reset_progress(0);
+        self.spawn_load(async move {
+            let page = match client.search(&query, page).await {
+                Ok(page) => page,
+                Err(error) => {
+                    let _ = tx.send(LoadResult::Failed { generation, error });
+                    return;
+                }
+            };
+            let ids = take_unseen(&mut known, page.ids);
+            let _ = tx.send(LoadResult::SearchPage {
+                generation,
+                ids: ids.clone(),
+                has_more: page.has_more,
+            });
+            // Algolia's hits lack comment ids, so the stories come from the HN API like
+            // a category's do.
+            let failures = stream_batch(&client, &ids, &progress, generation, &tx).await;
+            let _ = tx.send(LoadResult::Done {
+                generation,
+                end: known.len(),
+                failures,
+            });
+        });
+    }
+
+    /// Reloads the current category from scratch, keeping the selection on the same
+    /// story if it is still in the first batch.
+    pub fn refresh(&mut self) {
+        self.refresh_up_to(self.first_batch_len());
+    }
+
+    /// Like `refresh`, reloading the first `len` stories of the category.
+    fn refresh_up_to(&mut self, len: usize) {
+        if let Some(id) = self.single_item {
+            self.load_single_item(id);
+            return;
+        }
+        if let Some(search) = &self.remote_search {
+            self.search_hn(search.query.clone());
+            return;
         }
+        let selected_id = self.selected_story().map(|s| s.id);
+        self.positions
+            .insert(self.story_type, (self.selected_index, self.scroll_offset));
+        self.load_stories_up_to(len);
+        self.reselect_id = selected_id;
     }
 
-    pub async fn load_more_stories(&mut self) {
-        if self.loaded_count >= self.all_story_ids.len() {
+    /// Starts loading the next batch of the current category in the background.
+    pub fn load_more_stories(&mut self) {
+        if !matches!(self.state, AppState::Ready) || !self.can_load_more() {
+            return;
+        }
+        if self.remote_search.is_some() {
+            self.state = AppState::LoadingMore;
+            self.load_search_page();
             return;
         }
 
-        self.state = AppState::LoadingMore;
-        let next_batch = self.loaded_count.saturating_add(self.batch_size);
+        let next_batch = self.loaded_count.saturating_add(self.current_batch_size());
         let slice_end = next_batch.min(self.all_story_ids.len());
-
-        let ids_to_load = &self.all_story_ids[self.loaded_count..slice_end];
+        // The feed can shift between fetches (especially New), so ids from the next
+        // slice may already be loaded. Skip them, but still advance past the slice.
+        let ids_to_load: Vec<i64> = self.all_story_ids[self.loaded_count..slice_end]
+            .iter()
+            .copied()
+            .filter(|&id| !self.stories.iter().any(|s| s.id == id))
+            .collect();
 
         if ids_to_load.is_empty() {
-            self.state = AppState::Ready;
+            self.loaded_count = slice_end;
             return;
         }
 
-        let client = self.client.lock().await;
-        match client.get_stories_by_ids(ids_to_load).await {
-            Ok(mut stories) => {
-                self.stories.append(&mut stories);
-                self.loaded_count = slice_end;
-                self.state = AppState::Ready;
-            }
-            Err(e) => {
-                self.error_message = Some(e.to_string());
-                self.state = AppState::Error(e.to_string());
+        if self.offline {
+            self.apply_cached_batch(&ids_to_load, slice_end);
+            return;
+        }
+
+        self.state = AppState::LoadingMore;
+        let generation = self.load_generation;
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        let progress = self.reset_progress(ids_to_load.len());
+
+        self.spawn_load(async move {
+            let started = Instant::now();
+            let failures = stream_batch(&client, &ids_to_load, &progress, generation, &tx).await;
+            let timing = LoadTiming {
+                ids: None,
+                items: started.elapsed(),
+                count: ids_to_load.len(),
+            };
+            let _ = tx.send(LoadResult::Timing { generation, timing });
+            let _ = tx.send(LoadResult::Done {
+                generation,
+                end: slice_end,
+                failures,
+            });
+        });
+    }
+
+    /// Runs a category or batch fetch in the background. A fetch still running from an
+    /// earlier load is stopped, since its results would be ignored anyway.
+    fn spawn_load(&mut self, task: impl Future<Output = ()> + Send + 'static) {
+        if let Some(previous) = self.load_task.replace(tokio::spawn(task)) {
+            previous.abort();
+        }
+    }
+
+    /// Stops the load in progress and goes back to the list as it was. Stories from a
+    /// half-fetched batch are dropped, so the next "load more" fetches the batch whole.
+    pub fn cancel_load(&mut self) {
+        if !self.is_loading() {
+            return;
+        }
+        if let Some(task) = self.load_task.take() {
+            task.abort();
+        }
+        // Results the task already sent are ignored from here on.
+        self.load_generation += 1;
+        if self.single_item.is_none() {
+            self.keep_selected(|app| {
+                let end = app.loaded_count.min(app.all_story_ids.len());
+                let ids = &app.all_story_ids;
+                app.stories
+                    .retain(|s| ids.iter().position(|&id| id == s.id).is_none_or(|rank| rank < end));
+            });
+        }
+        // A half-fetched page of search results is fetched again by the next "load more".
+        if let Some(search) = &mut self.remote_search {
+            if self.all_story_ids.len() > self.loaded_count {
+                self.all_story_ids.truncate(self.loaded_count);
+                search.pages -= 1;
+                search.has_more = true;
             }
         }
+        self.reselect_id = None;
+        self.fresh_list = false;
+        // Nothing of a newly switched-to category arrived yet, so go back to the old one.
+        if let Some(previous) = self.previous_list.take().filter(|_| self.stories.is_empty()) {
+            self.restore_list(previous);
+        }
+        self.clamp_selection();
+        self.state = AppState::Ready;
+        if self.stories.is_empty() {
+            self.flash("Load cancelled; press r to retry".to_string());
+        } else {
+            self.flash("Load cancelled".to_string());
+        }
     }
 
-    pub fn can_load_more(&self) -> bool {
-        self.loaded_count < self.all_story_ids.len()
+    fn restore_list(&mut self, previous: PreviousList) {
+        self.story_type = previous.story_type;
+        self.list_type = previous.story_type;
+        self.stories = previous.stories;
+        self.all_story_ids = previous.all_story_ids;
+        self.loaded_count = previous.loaded_count;
+        self.load_total = previous.loaded_count;
+        self.selected_index = previous.selected_index;
+        self.scroll_offset = previous.scroll_offset;
+        self.single_item = previous.single_item;
+        self.remote_search = previous.remote_search;
     }
 
-    pub fn next_story(&mut self) {
-        if !self.stories.is_empty() {
-            self.selected_index = (self.selected_index + 1).min(self.stories.len() - 1);
-            self.update_scroll();
+    /// Shows the first `len` of the current category's cached stories. Offline, this
+    /// is the whole load. Saved stories come from the bookmarks instead of a feed.
+    fn load_cached_stories(&mut self, len: usize) {
+        let ids = match self.story_type {
+            StoryType::Saved => self.cache.available(&self.bookmarks),
+            story_type => self.cache.cached_ids(story_type),
+        };
+        let end = len.min(ids.len());
+        let batch = ids[..end].to_vec();
+        self.start_list(ids, end);
+        self.apply_cached_batch(&batch, end);
+    }
+
+    /// Adds cached stories the same way as streamed ones, so ranking and position
+    /// restore behave exactly as they do online.
+    fn apply_cached_batch(&mut self, ids: &[i64], end: usize) {
+        self.keep_selected(|app| {
+            for &id in ids {
+                if let Some(story) = app.cache.get(id).cloned() {
+                    app.insert_story(story);
+                }
+            }
+        });
+        self.apply_load_result(LoadResult::Done {
+            generation: self.load_generation,
+            end,
+            failures: 0,
+        });
+    }
+
+    /// Empties the list for a new id list and restores the category's remembered position.
+    fn start_list(&mut self, ids: Vec<i64>, end: usize) {
+        self.all_story_ids = ids;
+        self.loaded_count = end;
+        self.load_total = end;
+        self.stories.clear();
+        self.fresh_list = true;
+        let (selected, offset) = self.remembered_position();
+        self.selected_index = selected;
+        self.scroll_offset = offset;
+    }
+
+    /// How many stories each batch of the current category loads.
+    pub fn current_batch_size(&self) -> usize {
+        self.category_batch_sizes
+            .get(&self.story_type)
+            .copied()
+            .unwrap_or(self.batch_size)
+    }
+
+    fn first_batch_len(&self) -> usize {
+        self.first_batch_size
+            .unwrap_or_else(|| self.current_batch_size())
+    }
+
+    /// Starts a fresh progress counter for a new batch. Each load gets its own counter so
+    /// a superseded task can't bump the count shown for the current one.
+    fn reset_progress(&mut self, total: usize) -> Arc<AtomicUsize> {
+        self.load_progress = Arc::new(AtomicUsize::new(0));
+        self.load_total = total;
+        Arc::clone(&self.load_progress)
+    }
+
+    pub fn loaded_progress(&self) -> usize {
+        self.load_progress.load(Ordering::Relaxed)
+    }
+
+    /// Applies every load message that has arrived since the last call without blocking.
+    pub fn poll_loads(&mut self) {
+        while let Ok(result) = self.load_rx.try_recv() {
+            self.apply_load_result(result);
         }
     }
 
-    pub fn prev_story(&mut self) {
-        if !self.stories.is_empty() {
-            self.selected_index = self.selected_index.saturating_sub(1);
-            self.update_scroll();
+    /// Applies a message from a background load. Messages from loads that were
+    /// superseded by a newer `load_stories` call are discarded.
+    pub fn apply_load_result(&mut self, result: LoadResult) {
+        match result {
+            LoadResult::Ids {
+                generation,
+                ids,
+                end,
+            } if generation == self.load_generation => {
+                self.cache.set_ids(self.story_type, ids.clone());
+                self.last_loaded = Some(Utc::now());
+                if self.stories.is_empty() {
+                    self.start_list(ids, end);
+                } else {
+                    // Cached stories stay on screen until their fresh copies arrive. Drop
+                    // the ones that fell out of the batch and put the rest in the new order.
+                    self.reselect_id = self.reselect_id.or(self.selected_story().map(|s| s.id));
+                    self.keep_selected(|app| {
+                        app.all_story_ids = ids;
+                        app.loaded_count = end;
+                        app.load_total = end;
+                        let batch = &app.all_story_ids[..end];
+                        app.stories.retain(|s| batch.contains(&s.id));
+                        let rank = |id: i64| app.all_story_ids.iter().position(|&i| i == id);
+                        app.stories.sort_by_key(|s| rank(s.id));
+                    });
+                }
+            }
+            LoadResult::Story { generation, story } if generation == self.load_generation => {
+                self.cache.insert_story(&story);
+                self.keep_selected(|app| app.insert_story(story));
+                // Show the list as soon as there is something in it.
+                if matches!(self.state, AppState::Loading) {
+                    self.state = AppState::LoadingMore;
+                }
+            }
+            LoadResult::Timing { generation, timing } if generation == self.load_generation => {
+                self.last_timing = Some(timing);
+            }
+            LoadResult::Done {
+                generation,
+                end,
+                failures,
+            } if generation == self.load_generation => {
+                self.loaded_count = end;
+                self.last_load_failures = failures;
+                if self.story_type == StoryType::Saved && self.showing_category() {
+                    self.add_missing_bookmarks(end);
+                }
+                self.update_snapshot();
+                if let Some(id) = self.reselect_id.take() {
+                    self.reselect_by_id(id);
+                }
+                self.fresh_list = false;
+                self.previous_list = None;
+                self.clamp_selection();
+                self.state = AppState::Ready;
+            }
+            LoadResult::WarmIds { story_type, ids } => {
+                self.id_cache.insert(story_type, ids);
+            }
+            LoadResult::SearchPage {
+                generation,
+                ids,
+                has_more,
+            } if generation == self.load_generation => {
+                if let Some(search) = &mut self.remote_search {
+                    search.pages += 1;
+                    search.has_more = has_more;
+                }
+                self.load_total = ids.len();
+                self.all_story_ids.extend(ids);
+            }
+            LoadResult::Failed { generation, error } if generation == self.load_generation => {
+                self.fresh_list = false;
+                self.set_error(&error);
+            }
+            LoadResult::PollOptions { poll_id, options } => {
+                self.poll_options.insert(poll_id, options);
+            }
+            LoadResult::Comment(comment) => {
+                self.prefetched_comments.insert(comment.id, comment);
+            }
+            LoadResult::Updates(ids) if self.watch_updates => {
+                self.updated_ids = ids.into_iter().collect();
+            }
+            LoadResult::Comments { story_id, nodes } => {
+                if matches!(self.comments_view, Some(CommentsView::Loading(id)) if id == story_id) {
+                    self.comments_view = Some(CommentsView::Loaded(CommentThread {
+                        nodes,
+                        selected: 0,
+                    }));
+                }
+            }
+            LoadResult::Article { url, result } => {
+                // Ignore pages that arrive after the reader was closed or moved on.
+                if matches!(&self.reader, Some(ReaderView::Loading(loading)) if *loading == url) {
+                    self.reader = Some(match result {
+                        Ok(text) => ReaderView::Loaded(text),
+                        Err(e) => ReaderView::Error(e.to_string()),
+                    });
+                }
+            }
+            LoadResult::TopComment { story_id, text } => {
+                if let Some(story) = self.stories.iter_mut().find(|s| s.id == story_id) {
+                    story.top_comment = Some(text.clone());
+                }
+                if let Some(story) = self.cache.stories.get_mut(&story_id) {
+                    story.top_comment = Some(text);
+                }
+            }
+            LoadResult::User { username, result } => {
+                // Ignore profiles that arrive after the view was closed or moved on.
+                if matches!(&self.user_view, Some(UserView::Loading(name)) if *name == username) {
+                    self.user_view = Some(match result {
+                        Ok(user) => UserView::Loaded(user),
+                        Err(e) => UserView::Error(e.to_string()),
+                    });
+                }
+            }
+            _ => {}
         }
     }
 
-    pub fn page_down(&mut self) {
-        if !self.stories.is_empty() {
-            let page_size = 10;
-            self.selected_index = (self.selected_index + page_size).min(self.stories.len() - 1);
-            self.update_scroll();
+    /// Records how each loaded story changed since the previous load that included it,
+    /// then makes the current numbers the baseline for the next one.
+    fn update_snapshot(&mut self) {
+        for story in &self.stories {
+            let current = (story.score, story.descendant.unwrap_or(0));
+            if let Some(previous) = self.previous_snapshot.insert(story.id, current) {
+                if previous != current {
+                    self.score_deltas
+                        .insert(story.id, (current.0 - previous.0, current.1 - previous.1));
+                }
+            }
         }
     }
 
-    pub fn page_up(&mut self) {
-        if !self.stories.is_empty() {
-            let page_size = 10;
-            self.selected_index = self.selected_index.saturating_sub(page_size);
-            self.update_scroll();
+    /// How much a story's score and comment count grew in the latest refresh. Zero for
+    /// stories that weren't in the previous load.
+    pub fn delta(&self, id: i64) -> (i64, i64) {
+        self.score_deltas.get(&id).copied().unwrap_or((0, 0))
+    }
+
+    /// Shows a placeholder for each bookmark in the first `end` that didn't load, so
+    /// they can still be unbookmarked. Bookmarks of removed stories already arrived
+    /// as deleted placeholders; the rest failed for some other reason, e.g. a timeout.
+    fn add_missing_bookmarks(&mut self, end: usize) {
+        let missing: Vec<i64> = self.all_story_ids[..end.min(self.all_story_ids.len())]
+            .iter()
+            .copied()
+            .filter(|&id| !self.stories.iter().any(|s| s.id == id))
+            .collect();
+        self.keep_selected(|app| {
+            for id in missing {
+                app.insert_story(Story::unavailable(id));
+            }
+        });
+    }
+
+    /// Inserts a newly arrived story at its rank in the category, since items within a
+    /// batch resolve in no particular order. A story that is already loaded, e.g. from
+    /// the cache, is replaced by the newer copy.
+    fn insert_story(&mut self, story: Story) {
+        if let Some(existing) = self.stories.iter_mut().find(|s| s.id == story.id) {
+            let top_comment = existing.top_comment.take();
+            *existing = Story {
+                top_comment: story.top_comment.or(top_comment),
+                ..story
+            };
+            return;
         }
+        let rank = |id: i64| self.all_story_ids.iter().position(|&i| i == id);
+        let story_rank = rank(story.id);
+        let pos = self
+            .stories
+            .partition_point(|s| rank(s.id) < story_rank);
+        self.stories.insert(pos, story);
     }
 
-    pub fn update_scroll(&mut self) {
-        let visible_rows = 20;
-        if self.selected_index >= self.scroll_offset + visible_rows {
-            self.scroll_offset = self.selected_index - visible_rows + 1;
-        } else if self.selected_index < self.scroll_offset {
-            self.scroll_offset = self.selected_index;
+    pub fn toggle_comment_previews(&mut self) {
+        self.show_comment_previews = !self.show_comment_previews;
+    }
+
+    /// Fetches the first comment of each story currently on screen that doesn't have a
+    /// preview yet. Stories scrolled out of view are left alone to save requests.
+    pub fn fetch_comment_previews(&mut self) {
+        if !self.show_comment_previews || self.offline {
+            return;
+        }
+
+        let indices = self.filtered_indices();
+        let visible = indices.iter().skip(self.scroll_offset).take(self.viewport_height);
+        let mut wanted = Vec::new();
+        for &i in visible {
+            let story = &self.stories[i];
+            if let Some(&kid) = story.kids.as_ref().and_then(|k| k.first()) {
+                if self.preview_requests.insert(story.id) {
+                    wanted.push((story.id, kid));
+                }
+            }
+        }
+
+        for (story_id, comment_id) in wanted {
+            let client = Arc::clone(&self.client);
+            let tx = self.load_tx.clone();
+            tokio::spawn(async move {
+                let comment = client.get_story(comment_id).await;
+                if let Some(text) = comment.ok().and_then(|c| c.text) {
+                    let text = html::to_plain_text(&text.replace("<p>", " "));
+                    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    let _ = tx.send(LoadResult::TopComment { story_id, text });
+                }
+            });
         }
     }
 
-    pub fn next_story_type(&mut self) {
-        self.story_type = match self.story_type {
-            StoryType::Top => StoryType::New,
-            StoryType::New => StoryType::Best,
-            StoryType::Best => StoryType::Show,
-            StoryType::Show => StoryType::Ask,
-            StoryType::Ask => StoryType::Top,
+    /// Fetches the options of the selected story once it is shown in details, if it
+    /// is a poll.
+    pub fn fetch_poll_options(&mut self) {
+        if !self.details_visible() || self.offline {
+            return;
+        }
+        let Some(story) = self.selected_story().filter(|s| s.is_poll()) else {
+            return;
         };
+        let (poll_id, parts) = (story.id, story.parts.clone().unwrap_or_default());
+        if parts.is_empty() || !self.poll_requests.insert(poll_id) {
+            return;
+        }
+
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        tokio::spawn(async move {
+            if let Ok((options, _)) = client.get_stories_by_ids(&parts).await {
+                let _ = tx.send(LoadResult::PollOptions { poll_id, options });
+            }
+        });
     }
 
-    pub fn toggle_details(&mut self) {
-        self.show_details = !self.show_details;
+    /// Fetches the top-level comments of the selected story once the selection has
+    /// rested on it for `PREFETCH_DELAY`, so opening them only has to fetch replies.
+    pub fn prefetch_comments(&mut self) {
+        let Some((story_id, kids)) = self.due_for_prefetch(Instant::now()) else {
+            return;
+        };
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        tokio::spawn(async move {
+            let (comment_tx, mut comment_rx) = mpsc::unbounded_channel();
+            let progress = AtomicUsize::new(0);
+            let fetch = client.get_stories_streaming(&kids, &progress, comment_tx);
+            let forward = async {
+                while let Some(comment) = comment_rx.recv().await {
+                    let _ = tx.send(LoadResult::Comment(comment));
+                }
+            };
+            futures::join!(fetch, forward);
+        });
+        self.prefetched.insert(story_id);
     }
 
-    pub fn selected_story(&self) -> Option<&Story> {
-        self.stories.get(self.selected_index)
+    /// The selected story and its comment ids, if it is time to prefetch them. Nothing
+    /// is prefetched offline or while a batch is loading, so it never competes with the
+    /// list for requests.
+    fn due_for_prefetch(&mut self, now: Instant) -> Option<(i64, Vec<i64>)> {
+        let story = self.selected_story()?;
+        let (id, kids) = (story.id, story.kids.clone());
+        match self.selection_since {
+            Some((selected, since)) if selected == id => {
+                if now.duration_since(since) < PREFETCH_DELAY {
+                    return None;
+                }
+            }
+            _ => {
+                self.selection_since = Some((id, now));
+                return None;
+            }
+        }
+        if self.offline || self.is_loading() || self.prefetched.contains(&id) {
+            return None;
+        }
+        kids.filter(|kids| !kids.is_empty()).map(|kids| (id, kids))
     }
 
-    pub fn selected_story_url(&self) -> Option<&String> {
-        self.selected_story().and_then(|s| s.url.as_ref())
+    /// Fetches the id lists of the other categories once the first one has loaded, so
+    /// switching categories doesn't wait on them. Runs once per session.
+    pub fn warm_up_ids(&mut self) {
+        let Some(feeds) = self.due_for_warm_up() else {
+            return;
+        };
+        self.ids_warmed_at = Some(Instant::now());
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        tokio::spawn(async move {
+            // One feed at a time, so a load started meanwhile doesn't have to share
+            // the connection with several of them.
+            for story_type in feeds {
+                if let Ok(ids) = client.get_story_ids(story_type).await {
+                    let _ = tx.send(LoadResult::WarmIds { story_type, ids });
+                }
+            }
+        });
     }
 
-    pub fn has_selected_story_url(&self) -> bool {
-        self.selected_story_url().is_some()
+    /// The categories to warm up, if it is time to. Waits until nothing is loading so
+    /// the warm-up never slows down the first category.
+    fn due_for_warm_up(&self) -> Option<Vec<StoryType>> {
+        if self.ids_warmed_at.is_some() || self.offline || !matches!(self.state, AppState::Ready) {
+            return None;
+        }
+        Some(
+            api::ALL_FEEDS
+                .into_iter()
+                .filter(|&feed| feed != self.story_type)
+                .collect(),
+        )
     }
 
-    pub fn story_type_name(&self) -> &str {
-        match self.story_type {
-            StoryType::Top => "Top",
-            StoryType::New => "New",
-            StoryType::Best => "Best",
-            StoryType::Show => "Show",
-            StoryType::Ask => "Ask",
+    /// The warmed-up id list of `story_type`, unless it has gone stale. Either way it
+    /// is dropped, so refreshes fetch the feed again.
+    fn take_warm_ids(&mut self, story_type: StoryType) -> Option<Vec<i64>> {
+        let fresh = self
+            .ids_warmed_at
+            .is_some_and(|at| at.elapsed() < WARM_IDS_MAX_AGE);
+        self.id_cache.remove(&story_type).filter(|_| fresh)
+    }
+
+    /// Opens the profile view for the selected story's author and fetches it in the
+    /// background.
+    pub fn load_user(&mut self) {
+        let Some(story) = self.selected_story() else {
+            return;
+        };
+        let username = match story.by.as_deref() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => {
+                self.user_view = Some(UserView::Error("This story has no author".to_string()));
+                return;
+            }
+        };
+
+        if self.offline {
+            self.user_view = Some(UserView::Error("Profiles aren't available offline".to_string()));
+            return;
         }
+
+        self.user_view = Some(UserView::Loading(username.clone()));
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+
+        tokio::spawn(async move {
+            let result = client.get_user(&username).await;
+            let _ = tx.send(LoadResult::User { username, result });
+        });
     }
-}
 
-impl Default for App {
-    fn default() -> Self {
-        Self::new()
+    /// Opens the comment view for the selected story and fetches its whole thread in
+    /// the background. Counts as a visit for the new-comment badge.
+    pub fn open_comments(&mut self) {
+        let Some(story) = self.selected_story() else {
+            return;
+        };
+        let (story_id, kids) = (story.id, story.kids.clone().unwrap_or_default());
+        if self.offline {
+            self.comments_view = Some(CommentsView::Error(
+                "Comments aren't available offline".to_string(),
+            ));
+            return;
+        }
+
+        self.comments_view = Some(CommentsView::Loading(story_id));
+        self.mark_comments_seen();
+        let known: HashMap<i64, Story> = kids
+            .iter()
+            .filter_map(|id| self.prefetched_comments.get(id).map(|c| (*id, c.clone())))
+            .collect();
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        tokio::spawn(async move {
+            let nodes = client.get_comment_tree(&kids, &known).await;
+            let _ = tx.send(LoadResult::Comments { story_id, nodes });
+        });
+    }
+
+    pub fn close_comments(&mut self) {
+        self.comments_view = None;
+    }
+
+    pub fn next_comment(&mut self) {
+        if let Some(CommentsView::Loaded(thread)) = &mut self.comments_view {
+            let count = comments::visible(&thread.nodes).len();
+            thread.selected = (thread.selected + 1).min(count.saturating_sub(1));
+        }
+    }
+
+    pub fn prev_comment(&mut self) {
+        if let Some(CommentsView::Loaded(thread)) = &mut self.comments_view {
+            thread.selected = thread.selected.saturating_sub(1);
+        }
+    }
+
+    /// Collapses the selected comment and its replies, or expands it again.
+    pub fn toggle_comment(&mut self) {
+        if let Some(CommentsView::Loaded(thread)) = &mut self.comments_view {
+            if let Some(node) = comments::visible_mut(&mut thread.nodes, thread.selected) {
+                node.collapsed = !node.collapsed;
+            }
+        }
+    }
+
+    /// Opens the selected story's article in the reader and fetches it in the
+    /// background. Counts as reading the story.
+    pub fn open_reader(&mut self) {
+        let Some(story) = self.selected_story() else {
+            return;
+        };
+        let (id, url) = (story.id, story.url.clone());
+        self.reader_scroll = 0;
+        let Some(url) = url else {
+            self.reader = Some(ReaderView::Error(
+                "This story has no article — press O for the discussion".to_string(),
+            ));
+            return;
+        };
+        if self.offline {
+            self.reader = Some(ReaderView::Error("Articles aren't available offline".to_string()));
+            return;
+        }
+
+        self.reader = Some(ReaderView::Loading(url.clone()));
+        self.mark_read(id);
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        tokio::spawn(async move {
+            let result = client.fetch_article(&url).await;
+            let _ = tx.send(LoadResult::Article { url, result });
+        });
+    }
+
+    pub fn close_reader(&mut self) {
+        self.reader = None;
+    }
+
+    pub fn scroll_reader_down(&mut self, lines: u16) {
+        self.reader_scroll = self.reader_scroll.saturating_add(lines).min(self.reader_max_scroll);
+    }
+
+    pub fn scroll_reader_up(&mut self, lines: u16) {
+        self.reader_scroll = self.reader_scroll.saturating_sub(lines);
+    }
+
+    /// Sets how far the reader can scroll for the current page and terminal size.
+    pub fn set_reader_max_scroll(&mut self, max: u16) {
+        self.reader_max_scroll = max;
+        self.reader_scroll = self.reader_scroll.min(max);
+    }
+
+    /// Refreshes the New feed once `auto_refresh` has passed since it was last loaded.
+    /// Any load, manual or automatic, restarts the timer. Every story already loaded
+    /// is reloaded, so the list doesn't shrink back to one batch under the reader.
+    pub fn auto_refresh(&mut self) {
+        if self.auto_refresh_due(Instant::now()) {
+            self.refresh_up_to(self.loaded_count.max(self.first_batch_len()));
+        }
+    }
+
+    fn auto_refresh_due(&self, now: Instant) -> bool {
+        self.auto_refreshing()
+            && matches!(self.state, AppState::Ready)
+            && self
+                .auto_refresh
+                .is_some_and(|every| now.duration_since(self.last_refresh) >= every)
+    }
+
+    pub fn toggle_watch_updates(&mut self) {
+        self.watch_updates = !self.watch_updates;
+        self.updated_ids.clear();
+        self.updates_polled = None;
+    }
+
+    /// Whether a loaded story showed up in the latest changed-items poll.
+    pub fn is_updated(&self, id: i64) -> bool {
+        self.watch_updates && self.updated_ids.contains(&id)
+    }
+
+    /// Requests the changed-items feed every `UPDATES_INTERVAL` while watching for
+    /// updates, starting right away.
+    pub fn poll_updates(&mut self) {
+        if !self.updates_due(Instant::now()) {
+            return;
+        }
+        self.updates_polled = Some(Instant::now());
+        let client = Arc::clone(&self.client);
+        let tx = self.load_tx.clone();
+        tokio::spawn(async move {
+            if let Ok(ids) = client.get_updates().await {
+                let _ = tx.send(LoadResult::Updates(ids));
+            }
+        });
+    }
+
+    fn updates_due(&self, now: Instant) -> bool {
+        self.watch_updates
+            && !self.offline
+            && self
+                .updates_polled
+                .is_none_or(|at| now.duration_since(at) >= UPDATES_INTERVAL)
+    }
+
+    /// Whether the list is the current category, rather than one item or search results.
+    fn showing_category(&self) -> bool {
+        self.single_item.is_none() && self.remote_search.is_none()
+    }
+
+    /// Whether the feed on screen refreshes itself, for the header indicator.
+    pub fn auto_refreshing(&self) -> bool {
+        self.auto_refresh.is_some()
+            && self.story_type == StoryType::New
+            && self.showing_category()
+            && !self.offline
+    }
+
+    pub fn close_user_view(&mut self) {
+        self.user_view = None;
+    }
+
+    pub fn is_loading(&self) -> bool {
+        matches!(self.state, AppState::Loading | AppState::LoadingMore)
+    }
+
+    /// Advances the loading spinner, expires an unconfirmed quit and resets the details
+    /// scroll when the selection moves to another story. Called once per pass of the
+    /// event loop.
+    pub fn tick(&mut self) {
+        let selected = self.selected_story().map(|s| s.id);
+        if selected != self.details_scroll_id {
+            self.details_scroll_id = selected;
+            self.details_scroll = 0;
+        }
+        if self.is_loading() {
+            self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
+        }
+        if self
+            .pending_quit
+            .is_some_and(|at| at.elapsed() >= QUIT_CONFIRM_WINDOW)
+        {
+            self.pending_quit = None;
+            self.clear_status();
+        }
+        if self.status_expires.is_some_and(|at| Instant::now() >= at) {
+            self.clear_status();
+        }
+    }
+
+    /// Shows a status message for `FLASH_DURATION`.
+    pub fn flash(&mut self, message: String) {
+        self.set_status(message);
+        self.status_expires = Some(Instant::now() + FLASH_DURATION);
+    }
+
+    /// Shows a status message until the next key press. Any earlier flash's expiry is
+    /// dropped so it can't cut this message short.
+    pub fn set_status(&mut self, message: String) {
+        self.status_message = Some(message);
+        self.status_expires = None;
+    }
+
+    pub fn clear_status(&mut self) {
+        self.status_message = None;
+        self.status_expires = None;
+    }
+
+    /// Runs a command from the `:` prompt. Opening links and quitting belong to the
+    /// event loop, which handles them before getting here.
+    pub fn run_command(&mut self, command: Command) {
+        match command {
+            Command::Page(page) => self.jump_to_page(page),
+            Command::Category(story_type) => {
+                self.positions
+                    .insert(self.story_type, (self.selected_index, self.scroll_offset));
+                self.story_type = story_type;
+                self.load_stories();
+            }
+            Command::Limit(limit) => {
+                self.first_batch_size = Some(limit);
+                self.refresh();
+            }
+            Command::Sort(mode) => self.update_view(|app| app.sort_mode = mode),
+            Command::Search(query) => self.search_hn(query),
+            Command::Refresh => self.refresh(),
+            Command::Open | Command::Quit => {}
+        }
+    }
+
+    /// Handles `q`. Returns true if the app should exit now; with `confirm_quit` on, the
+    /// first press only asks for confirmation. `pending` is the previous press, if any
+    /// key since then didn't cancel it.
+    pub fn should_quit(&mut self, pending: Option<Instant>) -> bool {
+        if !self.confirm_quit || pending.is_some_and(|at| at.elapsed() < QUIT_CONFIRM_WINDOW) {
+            return true;
+        }
+        self.pending_quit = Some(Instant::now());
+        self.set_status("Press q again to quit".to_string());
+        false
+    }
+
+    pub fn spinner(&self) -> &'static str {
+        SPINNER_FRAMES[self.spinner_frame % SPINNER_FRAMES.len()]
+    }
+
+    fn set_error(&mut self, error: &anyhow::Error) {
+        let message = match (api::api_error(error), &self.proxy) {
+            (Some(ApiError::Network(e)), Some(proxy)) if e.is_connect() => {
+                format!("Could not connect through proxy {} — press r to retry", proxy)
+            }
+            (Some(e), _) => format!("{} — {}", e, e.hint()),
+            (None, _) => error.to_string(),
+        };
+        self.error_message = Some(message.clone());
+        self.state = AppState::Error(message);
+    }
+
+    pub fn can_load_more(&self) -> bool {
+        match &self.remote_search {
+            Some(search) => search.has_more,
+            None => self.loaded_count < self.all_story_ids.len(),
+        }
+    }
+
+    /// Whether a story passes every active filter. Deleted stories are never shown.
+    fn is_visible(&self, story: &Story) -> bool {
+        // Deleted bookmarks stay listed so they can be removed.
+        if story.is_deleted() && self.story_type != StoryType::Saved {
+            return false;
+        }
+        if self.hide_untitled && !story.has_title() && !story.is_deleted() {
+            return false;
+        }
+        if story.score < self.min_score || (self.hide_read && self.is_read(story.id)) {
+            return false;
+        }
+        if self.domain_filter.as_ref().is_some_and(|domain| story.domain() != *domain) {
+            return false;
+        }
+        !self.is_searching() || self.title_match(story).is_some()
+    }
+
+    fn is_searching(&self) -> bool {
+        self.search_query.as_deref().is_some_and(|q| !q.trim().is_empty())
+    }
+
+    /// How a story's title fuzzily matches the search query, if there is one. Positions
+    /// index the title with runs of whitespace collapsed to single spaces, as it is drawn.
+    pub fn title_match(&self, story: &Story) -> Option<Match> {
+        let query = self.search_query.as_deref()?;
+        let title = story.title.as_deref().unwrap_or_default();
+        fuzzy::fuzzy_match(query, &title.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+
+    /// Indices into `stories` that pass the active filters, in display order
+    /// according to the current sort mode. While searching, the default order ranks
+    /// stories by how well their titles match.
+    pub fn filtered_indices(&self) -> Vec<usize> {
+        let mut indices: Vec<usize> = self
+            .stories
+            .iter()
+            .enumerate()
+            .filter(|(_, story)| self.is_visible(story))
+            .map(|(i, _)| i)
+            .collect();
+
+        // Stable sorts, so ties keep HN's ranking.
+        let stories = &self.stories;
+        match self.sort_mode {
+            SortMode::Default if self.is_searching() => indices.sort_by_cached_key(|&i| {
+                Reverse(self.title_match(&stories[i]).map_or(0, |m| m.score))
+            }),
+            SortMode::Default => {}
+            SortMode::Score => indices.sort_by_key(|&i| Reverse(stories[i].score)),
+            SortMode::Comments => {
+                indices.sort_by_key(|&i| Reverse(stories[i].descendant.unwrap_or(0)))
+            }
+            SortMode::Time => indices.sort_by_key(|&i| Reverse(stories[i].time)),
+        }
+        indices
+    }
+
+    pub fn visible_count(&self) -> usize {
+        self.filtered_indices().len()
+    }
+
+    /// Moves the selection to story `id` wherever it now is in the visible list. The
+    /// selection stays put if the story isn't visible.
+    pub fn reselect_by_id(&mut self, id: i64) {
+        if let Some(pos) = self
+            .filtered_indices()
+            .iter()
+            .position(|&i| self.stories[i].id == id)
+        {
+            self.selected_index = pos;
+            self.update_scroll();
+        }
+    }
+
+    /// Applies a change to `self.stories` that can move stories around, e.g. a story
+    /// streaming in during a refresh, and then finds the selected story again by id.
+    /// Any change to the list should go through this or `update_view`. While a fresh
+    /// list streams in, only a story a refresh is waiting to re-select is followed.
+    fn keep_selected(&mut self, change: impl FnOnce(&mut Self)) {
+        let selected = if self.fresh_list {
+            self.reselect_id
+        } else {
+            self.selected_story().map(|s| s.id)
+        };
+        change(self);
+        if let Some(id) = selected {
+            self.reselect_by_id(id);
+        }
+    }
+
+    /// Applies a change to the filters or ordering while keeping the selection on the
+    /// same story. If that story is hidden by the change, the selection snaps to the
+    /// nearest story that is still visible, looking further down the list first.
+    fn update_view(&mut self, change: impl FnOnce(&mut Self)) {
+        let before: Vec<i64> = self
+            .filtered_indices()
+            .iter()
+            .map(|&i| self.stories[i].id)
+            .collect();
+        let selected = self.selected_index.min(before.len().saturating_sub(1));
+
+        change(self);
+
+        let after: Vec<i64> = self
+            .filtered_indices()
+            .iter()
+            .map(|&i| self.stories[i].id)
+            .collect();
+        let candidates = before
+            .iter()
+            .skip(selected)
+            .chain(before.iter().take(selected).rev());
+        self.selected_index = candidates
+            .filter_map(|id| after.iter().position(|a| a == id))
+            .next()
+            .unwrap_or(0);
+        self.update_scroll();
+    }
+
+    pub fn set_search(&mut self, q: String) {
+        self.update_view(|app| app.search_query = if q.is_empty() { None } else { Some(q) });
+    }
+
+    pub fn cycle_sort_mode(&mut self) {
+        self.update_view(|app| app.sort_mode = app.sort_mode.next());
+    }
+
+    pub fn raise_min_score(&mut self) {
+        self.update_view(|app| app.min_score += MIN_SCORE_STEP);
+    }
+
+    pub fn lower_min_score(&mut self) {
+        self.update_view(|app| app.min_score = (app.min_score - MIN_SCORE_STEP).max(0));
+    }
+
+    pub fn toggle_hide_read(&mut self) {
+        self.update_view(|app| app.hide_read = !app.hide_read);
+    }
+
+    /// Narrows the list to the stories from the selected story's domain, to see what
+    /// else a site has on the front page.
+    pub fn filter_by_domain(&mut self) {
+        if let Some(domain) = self.selected_story().map(|s| s.domain()) {
+            self.update_view(|app| app.domain_filter = Some(domain));
+        }
+    }
+
+    /// Handles `Esc` in the story list. It only drops a rank being typed if there is
+    /// one; otherwise it cancels a load in progress, or else clears the filters.
+    pub fn escape(&mut self, typing_rank: bool) {
+        if typing_rank {
+            return;
+        }
+        if self.is_loading() {
+            self.cancel_load();
+        } else {
+            self.clear_search();
+            self.clear_domain_filter();
+        }
+    }
+
+    pub fn clear_domain_filter(&mut self) {
+        self.update_view(|app| app.domain_filter = None);
+    }
+
+    pub fn clear_search(&mut self) {
+        self.searching = false;
+        self.set_search(String::new());
+    }
+
+    pub fn next_story(&mut self) {
+        let count = self.visible_count();
+        if count > 0 {
+            self.selected_index = if self.wrap_navigation && self.selected_index + 1 >= count {
+                0
+            } else {
+                (self.selected_index + 1).min(count - 1)
+            };
+            self.update_scroll();
+            self.maybe_auto_load();
+        }
+    }
+
+    pub fn prev_story(&mut self) {
+        let count = self.visible_count();
+        if count > 0 {
+            self.selected_index = if self.wrap_navigation && self.selected_index == 0 {
+                count - 1
+            } else {
+                self.selected_index.saturating_sub(1)
+            };
+            self.update_scroll();
+        }
+    }
+
+    pub fn page_down(&mut self) {
+        self.move_down(self.page_size());
+    }
+
+    /// Moves half a screen down, like vim's Ctrl-D.
+    pub fn half_page_down(&mut self) {
+        self.move_down((self.viewport_height / 2).max(1));
+    }
+
+    fn move_down(&mut self, rows: usize) {
+        let count = self.visible_count();
+        if count > 0 {
+            self.selected_index = (self.selected_index + rows).min(count - 1);
+            self.update_scroll();
+            self.maybe_auto_load();
+        }
+    }
+
+    /// Loads the next batch once the selection is within `auto_load_threshold` stories
+    /// of the end of the list. `load_more_stories` is a no-op while a load is in flight,
+    /// so repeated key presses near the end don't start overlapping loads.
+    fn maybe_auto_load(&mut self) {
+        if self.auto_load_threshold == 0 || self.is_loading() || !self.can_load_more() {
+            return;
+        }
+        let remaining = self.visible_count().saturating_sub(self.selected_index + 1);
+        if remaining < self.auto_load_threshold {
+            self.load_more_stories();
+        }
+    }
+
+    pub fn page_up(&mut self) {
+        self.move_up(self.page_size());
+    }
+
+    /// Moves half a screen up, like vim's Ctrl-U.
+    pub fn half_page_up(&mut self) {
+        self.move_up((self.viewport_height / 2).max(1));
+    }
+
+    fn move_up(&mut self, rows: usize) {
+        if self.visible_count() > 0 {
+            self.selected_index = self.selected_index.saturating_sub(rows);
+            self.update_scroll();
+        }
+    }
+
+    /// The 1-based page the selection is on and the number of pages, where a page is
+    /// one screenful of stories.
+    pub fn page(&self) -> (usize, usize) {
+        let pages = self.visible_count().div_ceil(self.viewport_height).max(1);
+        (self.selected_index / self.viewport_height + 1, pages)
+    }
+
+    /// Selects the first story of a 1-based page and scrolls it to the top. Pages past
+    /// either end are clamped.
+    pub fn jump_to_page(&mut self, page: usize) {
+        let count = self.visible_count();
+        if count == 0 {
+            return;
+        }
+        let (_, pages) = self.page();
+        let page = page.clamp(1, pages);
+        self.selected_index = ((page - 1) * self.viewport_height).min(count - 1);
+        self.scroll_offset = self.selected_index;
+        self.update_scroll();
+        self.maybe_auto_load();
+    }
+
+    /// Selects the story at `position` in the filtered list, if there is one.
+    pub fn select(&mut self, position: usize) {
+        if position < self.visible_count() {
+            self.selected_index = position;
+            self.update_scroll();
+        }
+    }
+
+    /// Where a loaded story ranks in its category, 1-based, as shown in the list.
+    pub fn rank(&self, story_idx: usize) -> usize {
+        let id = self.stories[story_idx].id;
+        self.all_story_ids
+            .iter()
+            .position(|&i| i == id)
+            .unwrap_or(story_idx)
+            + 1
+    }
+
+    /// Handles a digit key, given the rank typed so far. With the details popup open
+    /// and no rank being typed, 1-9 pick one of the story's numbered links, which is
+    /// returned for the caller to open. Otherwise the digit extends the rank.
+    pub fn press_digit(&mut self, mut typed: String, digit: char) -> Option<usize> {
+        if typed.is_empty() && self.show_details && digit != '0' {
+            return digit.to_digit(10).map(|n| n as usize);
+        }
+        typed.push(digit);
+        self.number_buffer = typed;
+        None
+    }
+
+    /// Selects the story shown with `rank`. If it isn't in the list, the story at that
+    /// position is selected instead, clamped to the last one.
+    pub fn jump_to_rank(&mut self, rank: usize) {
+        let indices = self.filtered_indices();
+        if indices.is_empty() {
+            return;
+        }
+        self.selected_index = indices
+            .iter()
+            .position(|&i| self.rank(i) == rank)
+            .unwrap_or_else(|| rank.clamp(1, indices.len()) - 1);
+        self.update_scroll();
+        self.maybe_auto_load();
+    }
+
+    pub fn select_first(&mut self) {
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+    }
+
+    pub fn select_last(&mut self) {
+        let count = self.visible_count();
+        if count > 0 {
+            self.selected_index = count - 1;
+            self.update_scroll();
+        }
+    }
+
+    /// Records how many rows the story list has, and scrolls so the selection stays on
+    /// screen if it shrank.
+    pub fn set_viewport_height(&mut self, rows: usize) {
+        let rows = rows.max(1);
+        if rows != self.viewport_height {
+            self.viewport_height = rows;
+            self.update_scroll();
+        }
+    }
+
+    /// Stories moved by PageUp/PageDown: a screenful, keeping one row of context.
+    fn page_size(&self) -> usize {
+        self.viewport_height.saturating_sub(1).max(1)
+    }
+
+    pub fn update_scroll(&mut self) {
+        let visible_rows = self.viewport_height;
+        if self.selected_index >= self.scroll_offset + visible_rows {
+            self.scroll_offset = self.selected_index - visible_rows + 1;
+        } else if self.selected_index < self.scroll_offset {
+            self.scroll_offset = self.selected_index;
+        }
+    }
+
+    /// The remembered selection and scroll offset for the current category.
+    fn remembered_position(&self) -> (usize, usize) {
+        self.positions
+            .get(&self.story_type)
+            .copied()
+            .unwrap_or((0, 0))
+    }
+
+    /// Clamps the selection to the stories that are actually visible, e.g. when a
+    /// remembered position is past the end of a category that now has fewer stories.
+    fn clamp_selection(&mut self) {
+        let last = self.visible_count().saturating_sub(1);
+        self.selected_index = self.selected_index.min(last);
+        self.scroll_offset = self.scroll_offset.min(self.selected_index);
+        self.update_scroll();
+    }
+
+    pub fn next_story_type(&mut self) {
+        self.positions
+            .insert(self.story_type, (self.selected_index, self.scroll_offset));
+        self.story_type = match self.story_type {
+            StoryType::Top => StoryType::New,
+            StoryType::New => StoryType::Best,
+            StoryType::Best => StoryType::Show,
+            StoryType::Show => StoryType::Ask,
+            StoryType::Ask => StoryType::All,
+            StoryType::All => StoryType::Saved,
+            StoryType::Saved => StoryType::Top,
+        };
+    }
+
+    /// The reverse of `next_story_type`.
+    pub fn prev_story_type(&mut self) {
+        self.positions
+            .insert(self.story_type, (self.selected_index, self.scroll_offset));
+        self.story_type = match self.story_type {
+            StoryType::Top => StoryType::Saved,
+            StoryType::New => StoryType::Top,
+            StoryType::Best => StoryType::New,
+            StoryType::Show => StoryType::Best,
+            StoryType::Ask => StoryType::Show,
+            StoryType::All => StoryType::Ask,
+            StoryType::Saved => StoryType::All,
+        };
+    }
+
+    /// Writes the story cache to disk so the next session can start from it. Comment
+    /// counts are pruned along with it, keeping only cached or bookmarked stories.
+    pub fn save_cache(&mut self) -> Result<()> {
+        self.cache.prune(&self.bookmarks);
+        self.comment_snapshot
+            .retain(|id, _| self.cache.get(*id).is_some() || self.bookmarks.contains(id));
+        if !self.persist {
+            return Ok(());
+        }
+        storage::save_story_cache(&self.cache)?;
+        storage::save_comment_snapshot(&self.comment_snapshot)
+    }
+
+    pub fn is_read(&self, id: i64) -> bool {
+        self.read.contains(&id)
+    }
+
+    /// Selects the next story that hasn't been opened, wrapping around to the top.
+    pub fn next_unread(&mut self) {
+        let indices = self.filtered_indices();
+        let count = indices.len();
+        let next = (1..=count)
+            .map(|step| (self.selected_index + step) % count)
+            .find(|&pos| !self.is_read(self.stories[indices[pos]].id));
+        match next {
+            Some(pos) => self.select(pos),
+            None => self.set_status("No unread stories".to_string()),
+        }
+    }
+
+    /// Marks a story read. When read stories are hidden, the selection moves on to the
+    /// next visible story.
+    pub fn mark_read(&mut self, id: i64) {
+        let mut inserted = false;
+        self.update_view(|app| inserted = app.read.insert(id));
+        if inserted && self.persist {
+            if let Err(e) = storage::save_read_stories(&self.read) {
+                self.set_status(format!("Failed to save read stories: {}", e));
+            }
+        }
+    }
+
+    /// Highlight rules take precedence over dim rules when both match.
+    pub fn domain_mark(&self, story: &Story) -> Option<DomainMark> {
+        let domain = story.domain();
+        let matches_any = |rules: &[String]| rules.iter().any(|rule| domain_matches(&domain, rule));
+        if matches_any(&self.highlight_domains) {
+            Some(DomainMark::Highlight)
+        } else if matches_any(&self.dim_domains) {
+            Some(DomainMark::Dim)
+        } else {
+            None
+        }
+    }
+
+    /// Writes the loaded stories to `path` in the configured export format.
+    pub fn export_stories(&self, path: &Path) -> Result<()> {
+        fs::write(path, render_export(&self.stories, self.export_format)?)?;
+        Ok(())
+    }
+
+    /// Exports the loaded stories to a timestamped file in the home directory and
+    /// reports where it went.
+    pub fn export(&mut self) {
+        let file_name = format!(
+            "hn-export-{}.{}",
+            Local::now().format("%Y%m%d-%H%M%S"),
+            self.export_format.extension()
+        );
+        let result = storage::home_dir()
+            .map(|home| home.join(file_name))
+            .ok_or_else(|| anyhow::anyhow!("could not determine home directory"))
+            .and_then(|path: PathBuf| self.export_stories(&path).map(|()| path));
+        self.set_status(match result {
+            Ok(path) => format!("Exported to {}", path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    /// Remembers the selected story's comment count as seen, clearing its new-comment
+    /// badge until more comments arrive.
+    pub fn mark_comments_seen(&mut self) {
+        let Some(story) = self.selected_story() else {
+            return;
+        };
+        let (id, count) = (story.id, story.descendant.unwrap_or(0));
+        if self.comment_snapshot.insert(id, count) == Some(count) || !self.persist {
+            return;
+        }
+        if let Err(e) = storage::save_comment_snapshot(&self.comment_snapshot) {
+            self.set_status(format!("Failed to save comment counts: {}", e));
+        }
+    }
+
+    /// How many comments `story` has gained since its discussion was last opened.
+    /// `None` for stories never opened or without new comments.
+    pub fn new_comments(&self, story: &Story) -> Option<i64> {
+        let seen = *self.comment_snapshot.get(&story.id)?;
+        let new = story.descendant.unwrap_or(0) - seen;
+        (new > 0).then_some(new)
+    }
+
+    pub fn is_bookmarked(&self, id: i64) -> bool {
+        self.bookmarks.contains(&id)
+    }
+
+    pub fn toggle_bookmark(&mut self) {
+        let Some(id) = self.selected_story().map(|s| s.id) else {
+            return;
+        };
+        if self.is_bookmarked(id) {
+            self.remove_bookmark(id);
+            return;
+        }
+
+        self.bookmarks.push(id);
+        self.save_bookmarks("Bookmarked");
+    }
+
+    /// Unbookmarks `id`. In the Saved category the story leaves the list right away.
+    pub fn remove_bookmark(&mut self, id: i64) {
+        let Some(pos) = self.bookmarks.iter().position(|&b| b == id) else {
+            return;
+        };
+        self.bookmarks.remove(pos);
+        if self.story_type == StoryType::Saved && self.showing_category() {
+            self.update_view(|app| {
+                app.stories.retain(|s| s.id != id);
+                if let Some(i) = app.all_story_ids.iter().position(|&s| s == id) {
+                    app.all_story_ids.remove(i);
+                    if i < app.loaded_count {
+                        app.loaded_count -= 1;
+                    }
+                }
+            });
+        }
+        self.save_bookmarks("Bookmark removed");
+    }
+
+    fn save_bookmarks(&mut self, message: &str) {
+        if !self.persist {
+            self.set_status(message.to_string());
+            return;
+        }
+        self.set_status(match storage::save_bookmarks(&self.bookmarks) {
+            Ok(()) => message.to_string(),
+            Err(e) => format!("Failed to save bookmarks: {}", e),
+        });
+    }
+
+    /// Adds the selected story to the end of the read-next queue.
+    pub fn enqueue(&mut self) {
+        let Some(id) = self.selected_story().map(|s| s.id) else {
+            return;
+        };
+        let message = if self.queue.contains(&id) {
+            "Already queued".to_string()
+        } else {
+            self.queue.push(id);
+            format!("Queued ({} to read)", self.queue.len())
+        };
+        self.set_status(self.save_queue().map_or_else(|e| e, |()| message));
+    }
+
+    /// Takes the oldest story off the read-next queue, marks it read and returns the
+    /// link to open: the story's own, or its discussion if it has none or isn't loaded.
+    pub fn dequeue(&mut self) -> Option<String> {
+        if self.queue.is_empty() {
+            self.set_status("The queue is empty — press a to add a story".to_string());
+            return None;
+        }
+        let id = self.queue.remove(0);
+        let url = self
+            .stories
+            .iter()
+            .find(|s| s.id == id)
+            .or_else(|| self.cache.get(id))
+            .and_then(|s| s.url.clone())
+            .unwrap_or_else(|| api::expand_item_url(&self.item_url_template, id));
+        self.mark_read(id);
+        let message = format!("{} left in the queue", self.queue.len());
+        self.set_status(self.save_queue().map_or_else(|e| e, |()| message));
+        Some(url)
+    }
+
+    /// Writes the queue to disk if it is configured to persist.
+    fn save_queue(&self) -> std::result::Result<(), String> {
+        if !self.persist_queue || !self.persist {
+            return Ok(());
+        }
+        storage::save_queue(&self.queue).map_err(|e| format!("Failed to save the queue: {}", e))
+    }
+
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    pub fn toggle_absolute_time(&mut self) {
+        self.show_absolute_time = !self.show_absolute_time;
+    }
+
+    pub fn toggle_details(&mut self) {
+        self.show_details = !self.show_details;
+    }
+
+    pub fn scroll_details_down(&mut self) {
+        self.details_scroll = (self.details_scroll + 1).min(self.details_max_scroll);
+    }
+
+    pub fn scroll_details_up(&mut self) {
+        self.details_scroll = self.details_scroll.saturating_sub(1);
+    }
+
+    /// Sets how far the details can scroll for the current story and terminal size,
+    /// pulling the offset back if it is now past the end.
+    pub fn set_details_max_scroll(&mut self, max: u16) {
+        self.details_max_scroll = max;
+        self.details_scroll = self.details_scroll.min(max);
+    }
+
+    pub fn toggle_layout(&mut self) {
+        self.layout_mode = match self.layout_mode {
+            LayoutMode::Single => LayoutMode::Split,
+            LayoutMode::Split => LayoutMode::Single,
+        };
+    }
+
+    /// Whether the details sit in a pane beside the list rather than filling the screen.
+    pub fn shows_details_pane(&self) -> bool {
+        self.layout_mode == LayoutMode::Split && !self.show_details
+    }
+
+    /// Whether the selected story's details are on screen in either layout.
+    pub fn details_visible(&self) -> bool {
+        self.show_details || self.layout_mode == LayoutMode::Split
+    }
+
+    pub fn selected_story(&self) -> Option<&Story> {
+        self.filtered_indices()
+            .get(self.selected_index)
+            .and_then(|&i| self.stories.get(i))
+    }
+
+    pub fn selected_story_url(&self) -> Option<&String> {
+        self.selected_story().and_then(|s| s.url.as_ref())
+    }
+
+    /// The selected story's discussion page, in the configured frontend.
+    pub fn selected_story_hn_url(&self) -> Option<String> {
+        self.selected_story().map(|s| api::expand_item_url(&self.item_url_template, s.id))
+    }
+
+    pub fn has_selected_story_url(&self) -> bool {
+        self.selected_story_url().is_some()
+    }
+
+    pub fn story_type_name(&self) -> &str {
+        match self.story_type {
+            StoryType::Top => "Top",
+            StoryType::New => "New",
+            StoryType::Best => "Best",
+            StoryType::Show => "Show",
+            StoryType::Ask => "Ask",
+            StoryType::All => "All",
+            StoryType::Saved => "Saved",
+        }
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Suffix match on whole labels, so a `github.com` rule matches `gist.github.com`
+/// but not `notgithub.com`.
+fn domain_matches(domain: &str, rule: &str) -> bool {
+    let domain = domain.to_lowercase();
+    let rule = rule.trim().trim_start_matches('.').to_lowercase();
+    !rule.is_empty() && (domain == rule || domain.ends_with(&format!(".{}", rule)))
+}
+
+/// Renders stories as a JSON array or as a Markdown list of links.
+fn render_export(stories: &[Story], format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Json => {
+            let exported: Vec<ExportedStory> = stories
+                .iter()
+                .map(|story| ExportedStory {
+                    title: story.display_title(),
+                    url: story.url.as_deref(),
+                    score: story.score,
+                    hn_url: story.hn_url(),
+                })
+                .collect();
+            Ok(serde_json::to_string_pretty(&exported)?)
+        }
+        ExportFormat::Markdown => {
+            let mut out = String::new();
+            for story in stories {
+                let title = story
+                    .display_title()
+                    .replace('[', "\\[")
+                    .replace(']', "\\]");
+                let hn_url = story.hn_url();
+                let url = story.url.as_deref().unwrap_or(&hn_url);
+                out.push_str(&format!(
+                    "- [{}](<{}>) — {} points ([discussion](<{}>))\n",
+                    title, url, story.score, hn_url
+                ));
+            }
+            Ok(out)
+        }
+    }
+}
+
+/// The ids not in `known` yet, which are added to it. Search results can shift between
+/// pages, so a later page may repeat a story already listed.
+fn take_unseen(known: &mut Vec<i64>, ids: Vec<i64>) -> Vec<i64> {
+    let mut unseen = Vec::new();
+    for id in ids {
+        if !known.contains(&id) {
+            known.push(id);
+            unseen.push(id);
+        }
+    }
+    unseen
+}
+
+/// Fetches `ids`, forwarding each story to the event loop as soon as it resolves.
+/// Returns how many items failed to load.
+async fn stream_batch(
+    client: &HackerNewsClient,
+    ids: &[i64],
+    progress: &AtomicUsize,
+    generation: u64,
+    tx: &mpsc::UnboundedSender<LoadResult>,
+) -> usize {
+    let (story_tx, mut story_rx) = mpsc::unbounded_channel();
+    let fetch = client.get_stories_streaming(ids, progress, story_tx);
+    let forward = async {
+        while let Some(story) = story_rx.recv().await {
+            let _ = tx.send(LoadResult::Story { generation, story });
+        }
+    };
+    let (failures, ()) = futures::join!(fetch, forward);
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ready_app() -> App {
+        let mut app = App::for_tests();
+        app.state = AppState::Ready;
+        app
+    }
+
+    fn app_with_stories(count: i64) -> App {
+        let mut app = ready_app();
+        for id in 1..=count {
+            app.stories.push(Story::fixture(id));
+        }
+        app
+    }
+
+    #[test]
+    fn shrinking_the_terminal_keeps_the_selection_on_screen() {
+        let mut app = app_with_stories(30);
+        for _ in 0..15 {
+            app.next_story();
+        }
+        assert_eq!(app.scroll_offset, 0);
+
+        app.set_viewport_height(5);
+
+        assert_eq!(app.selected_index, 15);
+        assert_eq!(app.scroll_offset, 11);
+    }
+
+    #[test]
+    fn paging_moves_by_the_viewport_height() {
+        let mut app = app_with_stories(100);
+        app.set_viewport_height(48);
+
+        app.page_down();
+        assert_eq!(app.selected_index, 47);
+        assert_eq!(app.scroll_offset, 0);
+
+        app.page_down();
+        assert_eq!(app.selected_index, 94);
+        assert_eq!(app.scroll_offset, 47);
+
+        app.page_up();
+        assert_eq!(app.selected_index, 47);
+        assert_eq!(app.scroll_offset, 47);
+    }
+
+    #[test]
+    fn offline_load_without_cache_shows_nothing() {
+        let mut app = App::for_tests();
+        app.offline = true;
+
+        // Any network load would panic here, since tests run without a tokio runtime.
+        app.load_stories();
+        app.load_more_stories();
+
+        assert!(matches!(app.state, AppState::Ready));
+        assert!(app.stories.is_empty());
+        assert!(!app.can_load_more());
+    }
+
+    #[test]
+    fn offline_load_pages_through_cached_stories() {
+        let cached = app_with_stories(5);
+        let mut app = App::for_tests();
+        app.batch_size = 2;
+        app.offline = true;
+        app.story_type = StoryType::Top;
+        for story in cached.stories.iter().filter(|s| s.id != 4) {
+            app.cache.insert_story(story);
+        }
+        // Story 4 was never fetched, so it can't be shown offline.
+        app.cache.set_ids(StoryType::Top, vec![5, 4, 3, 2, 1]);
+
+        app.load_stories();
+        let ids: Vec<i64> = app.stories.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![5, 3]);
+
+        app.load_more_stories();
+        app.load_more_stories();
+        let ids: Vec<i64> = app.stories.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![5, 3, 2, 1]);
+        assert!(!app.can_load_more());
+    }
+
+    #[test]
+    fn half_page_moves_by_half_the_viewport() {
+        let mut app = app_with_stories(100);
+        app.set_viewport_height(20);
+
+        app.half_page_down();
+        app.half_page_down();
+        app.half_page_down();
+        assert_eq!(app.selected_index, 30);
+        assert_eq!(app.scroll_offset, 11);
+
+        app.half_page_up();
+        assert_eq!(app.selected_index, 20);
+        assert_eq!(app.scroll_offset, 11);
+    }
+
+    #[test]
+    fn single_item_comes_from_the_cache_when_offline() {
+        let cached = app_with_stories(3);
+        let mut app = App::for_tests();
+        app.offline = true;
+        app.cache.insert_story(&cached.stories[1]);
+
+        app.load_single_item(2);
+        assert!(matches!(app.state, AppState::Ready));
+        assert_eq!(app.selected_story().map(|s| s.id), Some(2));
+
+        app.load_single_item(3);
+        assert!(matches!(app.state, AppState::Error(_)));
+        assert!(app.selected_story().is_none());
+    }
+
+    #[test]
+    fn next_unread_skips_read_stories_and_wraps() {
+        let mut app = app_with_stories(5);
+        app.read = [1, 3, 4].into_iter().collect();
+        app.select(1);
+
+        app.next_unread();
+        assert_eq!(app.selected_story().map(|s| s.id), Some(5));
+
+        app.next_unread();
+        assert_eq!(app.selected_story().map(|s| s.id), Some(2));
+
+        app.read.extend([2, 5]);
+        app.next_unread();
+        assert_eq!(app.selected_story().map(|s| s.id), Some(2));
+        assert!(app.status_message.is_some());
+    }
+
+    #[test]
+    fn a_later_status_message_outlasts_an_earlier_flash() {
+        let mut app = ready_app();
+        app.flash("Load cancelled".to_string());
+        app.status_expires = Some(Instant::now());
+        app.set_status("Saved".to_string());
+
+        app.tick();
+        assert_eq!(app.status_message.as_deref(), Some("Saved"));
+    }
+
+    #[test]
+    fn prefetch_waits_for_the_selection_to_rest() {
+        let mut app = app_with_stories(3);
+        app.stories[0].kids = Some(vec![10, 11]);
+        let start = Instant::now();
+
+        assert_eq!(app.due_for_prefetch(start), None);
+        assert_eq!(app.due_for_prefetch(start + Duration::from_millis(100)), None);
+
+        app.state = AppState::LoadingMore;
+        assert_eq!(app.due_for_prefetch(start + PREFETCH_DELAY), None);
+
+        app.state = AppState::Ready;
+        assert_eq!(
+            app.due_for_prefetch(start + PREFETCH_DELAY),
+            Some((1, vec![10, 11]))
+        );
+
+        // Moving away restarts the delay.
+        app.next_story();
+        assert_eq!(app.due_for_prefetch(start + PREFETCH_DELAY * 2), None);
+    }
+
+    #[test]
+    fn prefetched_comments_stay_out_of_the_story_cache() {
+        let mut app = app_with_stories(1);
+        let comment = Story {
+            r#type: "comment".to_string(),
+            ..Story::fixture(10)
+        };
+        app.apply_load_result(LoadResult::Comment(comment));
+        assert!(app.prefetched_comments.contains_key(&10));
+        assert!(app.cache.get(10).is_none());
+    }
+
+    #[test]
+    fn auto_refresh_waits_for_the_interval_on_new_only() {
+        let mut app = app_with_stories(2);
+        app.auto_refresh = Some(Duration::from_secs(60));
+        let start = app.last_refresh;
+
+        app.story_type = StoryType::Top;
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(61)));
+
+        app.story_type = StoryType::New;
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(30)));
+        assert!(app.auto_refresh_due(start + Duration::from_secs(61)));
+
+        app.state = AppState::LoadingMore;
+        assert!(!app.auto_refresh_due(start + Duration::from_secs(61)));
+    }
+
+    #[tokio::test]
+    async fn auto_refresh_reloads_every_loaded_batch() {
+        let mut app = app_with_stories(0);
+        app.offline = true;
+        app.story_type = StoryType::New;
+        for id in 1..=50 {
+            app.cache.insert_story(&Story::fixture(id));
+        }
+        app.cache.set_ids(StoryType::New, (1..=50).collect());
+        app.load_stories_up_to(40);
+        app.selected_index = 35;
+
+        app.offline = false;
+        app.auto_refresh = Some(Duration::ZERO);
+        app.auto_refresh();
+        // The refresh starts from the first 40 cached stories, so it is fetching 40.
+        assert!(app.is_loading());
+        assert_eq!(app.loaded_count, 40);
+        assert_eq!(app.stories.len(), 40);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(36));
+        app.cancel_load();
+    }
+
+    #[test]
+    fn details_scroll_is_clamped_and_resets_for_a_new_story() {
+        let mut app = app_with_stories(2);
+        app.tick();
+        app.set_details_max_scroll(2);
+
+        for _ in 0..5 {
+            app.scroll_details_down();
+        }
+        assert_eq!(app.details_scroll, 2);
+
+        app.set_details_max_scroll(1);
+        assert_eq!(app.details_scroll, 1);
+
+        app.next_story();
+        app.tick();
+        assert_eq!(app.details_scroll, 0);
+    }
+
+    #[test]
+    fn updates_mark_loaded_stories_only_while_watching() {
+        let mut app = app_with_stories(3);
+        app.apply_load_result(LoadResult::Updates(vec![2]));
+        assert!(!app.is_updated(2));
+
+        app.toggle_watch_updates();
+        assert!(app.updates_due(Instant::now()));
+        app.apply_load_result(LoadResult::Updates(vec![2, 99]));
+        assert!(app.is_updated(2));
+        assert!(!app.is_updated(1));
+
+        app.toggle_watch_updates();
+        assert!(!app.is_updated(2));
+    }
+
+    #[test]
+    fn queue_opens_stories_in_the_order_they_were_added() {
+        let mut app = app_with_stories(3);
+        app.stories[1].url = Some("https://example.com/two".to_string());
+        app.item_url_template = "https://hn.example.com/item/{id}".to_string();
+        app.select(1);
+        app.enqueue();
+        app.enqueue();
+        app.select(2);
+        app.enqueue();
+        assert_eq!(app.queue, vec![2, 3]);
+
+        assert_eq!(app.dequeue().as_deref(), Some("https://example.com/two"));
+        assert_eq!(app.dequeue().as_deref(), Some("https://hn.example.com/item/3"));
+        assert_eq!(app.dequeue(), None);
+        assert!(app.is_read(2) && app.is_read(3));
+    }
+
+    #[test]
+    fn saving_the_cache_prunes_comment_counts() {
+        let mut app = app_with_stories(0);
+        app.cache.insert_story(&Story::fixture(1));
+        app.cache.set_ids(StoryType::Top, vec![1]);
+        app.bookmarks = vec![2];
+        app.comment_snapshot = HashMap::from([(1, 5), (2, 3), (3, 8)]);
+
+        app.save_cache().unwrap();
+        let mut ids: Vec<i64> = app.comment_snapshot.keys().copied().collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[test]
+    fn new_comments_count_growth_since_the_last_visit() {
+        let mut app = app_with_stories(3);
+        app.stories[0].descendant = Some(12);
+        app.stories[1].descendant = Some(4);
+        app.comment_snapshot = HashMap::from([(1, 7), (2, 4)]);
+
+        assert_eq!(app.new_comments(&app.stories[0]), Some(5));
+        assert_eq!(app.new_comments(&app.stories[1]), None);
+        // Never opened, so there is nothing to compare against.
+        assert_eq!(app.new_comments(&app.stories[2]), None);
+    }
+
+    #[test]
+    fn comment_selection_stays_on_a_collapsed_comment() {
+        let mut app = app_with_stories(4);
+        let comment = |i: usize| app.stories[i].clone();
+        let nodes = vec![
+            CommentNode::new(comment(0), vec![CommentNode::new(comment(1), vec![])]),
+            CommentNode::new(comment(2), vec![CommentNode::new(comment(3), vec![])]),
+        ];
+        app.comments_view = Some(CommentsView::Loaded(CommentThread {
+            nodes,
+            selected: 0,
+        }));
+        let selected = |app: &App| match &app.comments_view {
+            Some(CommentsView::Loaded(thread)) => thread.selected,
+            _ => unreachable!(),
+        };
+
+        app.next_comment();
+        app.next_comment();
+        app.toggle_comment();
+        app.next_comment();
+        assert_eq!(selected(&app), 2);
+
+        app.prev_comment();
+        app.prev_comment();
+        app.toggle_comment();
+        app.next_comment();
+        app.next_comment();
+        assert_eq!(selected(&app), 1);
+    }
+
+    #[test]
+    fn reader_explains_why_it_has_nothing_to_show() {
+        let mut app = app_with_stories(2);
+        app.open_reader();
+        assert!(matches!(&app.reader, Some(ReaderView::Error(m)) if m.contains("no article")));
+
+        app.close_reader();
+        app.offline = true;
+        app.stories[1].url = Some("https://example.com/post".to_string());
+        app.select(1);
+        app.open_reader();
+        assert!(matches!(&app.reader, Some(ReaderView::Error(m)) if m.contains("offline")));
+    }
+
+    #[test]
+    fn load_timing_summarizes_ids_and_items() {
+        let timing = LoadTiming {
+            ids: Some(Duration::from_millis(120)),
+            items: Duration::from_millis(900),
+            count: 30,
+        };
+        assert_eq!(timing.summary(), "ids 120ms, 30 items 900ms (30ms/item)");
+
+        let more = LoadTiming { ids: None, count: 0, ..timing };
+        assert_eq!(more.summary(), "0 items 900ms");
+    }
+
+    #[test]
+    fn errors_say_what_went_wrong_and_what_to_do() {
+        let mut app = ready_app();
+        app.proxy = None;
+        app.set_error(&anyhow::Error::from(ApiError::BadStatus(503)));
+        assert_eq!(
+            app.error_message.as_deref(),
+            Some("HN returned 503 — try again in a moment")
+        );
+
+        app.set_error(&anyhow::Error::from(ApiError::Timeout));
+        assert_eq!(
+            app.error_message.as_deref(),
+            Some("Request timed out — press r to retry")
+        );
+    }
+
+    #[test]
+    fn jump_to_rank_selects_the_story_with_that_rank() {
+        let mut app = app_with_stories(5);
+        app.all_story_ids = (1..=5).collect();
+        app.hide_read = true;
+        app.read = HashSet::from([2]);
+
+        app.jump_to_rank(4);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(4));
+
+        // Rank 2 is hidden, so the second visible story is picked.
+        app.jump_to_rank(2);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(3));
+
+        app.jump_to_rank(99);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(5));
+    }
+
+    #[test]
+    fn refresh_records_deltas_for_stories_seen_before() {
+        let mut app = app_with_stories(2);
+        app.stories[0].score = 10;
+        app.update_snapshot();
+        assert_eq!(app.delta(1), (0, 0));
+
+        app.stories[0].score = 22;
+        app.stories[0].descendant = Some(3);
+        app.stories.push(Story {
+            id: 3,
+            score: 50,
+            ..app.stories[1].clone()
+        });
+        app.update_snapshot();
+
+        assert_eq!(app.delta(1), (12, 3));
+        assert_eq!(app.delta(2), (0, 0));
+        assert_eq!(app.delta(3), (0, 0));
+    }
+
+    #[test]
+    fn domain_filter_keeps_the_selected_story_and_its_siblings() {
+        let mut app = app_with_stories(4);
+        for (story, url) in app.stories.iter_mut().zip([
+            "https://blog.example.com/a",
+            "https://other.org/b",
+            "https://blog.example.com/c",
+            "https://www.blog.example.com/d",
+        ]) {
+            story.url = Some(url.to_string());
+        }
+        app.select(2);
+
+        app.filter_by_domain();
+        let ids: Vec<i64> = app.filtered_indices().iter().map(|&i| app.stories[i].id).collect();
+        assert_eq!(ids, vec![1, 3, 4]);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(3));
+
+        app.clear_domain_filter();
+        assert_eq!(app.visible_count(), 4);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(3));
+    }
+
+    #[test]
+    fn cancelling_a_load_drops_the_partial_batch() {
+        let mut app = app_with_stories(0);
+        app.start_list((1..=6).collect(), 3);
+        let generation = app.load_generation;
+        for id in 1..=4 {
+            app.apply_load_result(LoadResult::Story {
+                generation,
+                story: Story::fixture(id),
+            });
+        }
+        app.state = AppState::LoadingMore;
+
+        app.cancel_load();
+        assert!(matches!(app.state, AppState::Ready));
+        assert_eq!(app.loaded_count, 3);
+        let ids: Vec<i64> = app.stories.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+
+        // Whatever the aborted fetch already sent is ignored.
+        app.apply_load_result(LoadResult::Story {
+            generation,
+            story: Story::fixture(5),
+        });
+        app.apply_load_result(LoadResult::Done {
+            generation,
+            end: 6,
+            failures: 0,
+        });
+        assert_eq!(app.stories.len(), 3);
+        assert_eq!(app.loaded_count, 3);
+    }
+
+    #[test]
+    fn search_pages_append_without_repeats() {
+        let mut known = vec![5, 3];
+        assert_eq!(take_unseen(&mut known, vec![3, 9, 9]), vec![9]);
+        assert_eq!(known, vec![5, 3, 9]);
+
+        let mut app = app_with_stories(0);
+        app.remote_search = Some(RemoteSearch {
+            query: "rust".to_string(),
+            pages: 0,
+            has_more: false,
+        });
+        app.start_list(Vec::new(), 0);
+        let generation = app.load_generation;
+        let story = |id: i64| Story {
+            kids: Some(vec![id * 10]),
+            ..Story::fixture(id)
+        };
+        let mut page = |ids: Vec<i64>, has_more, end| {
+            app.apply_load_result(LoadResult::SearchPage {
+                generation,
+                ids: ids.clone(),
+                has_more,
+            });
+            // Items stream in from the HN API in whatever order they resolve.
+            for &id in ids.iter().rev() {
+                app.apply_load_result(LoadResult::Story { generation, story: story(id) });
+            }
+            app.apply_load_result(LoadResult::Done {
+                generation,
+                end,
+                failures: 0,
+            });
+        };
+        page(vec![5, 3], true, 2);
+        page(vec![9], false, 3);
+        assert!(!app.can_load_more());
+
+        let ids: Vec<i64> = app.stories.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![5, 3, 9]);
+        assert_eq!(app.stories[0].kids, Some(vec![50]));
+        assert_eq!(app.loaded_count, 3);
+        assert_eq!(app.remote_search.as_ref().map(|s| s.pages), Some(2));
+    }
+
+    #[test]
+    fn streamed_stories_do_not_move_the_selection() {
+        let mut app = app_with_stories(0);
+        app.start_list((1..=4).collect(), 2);
+        app.sort_mode = SortMode::Score;
+        let generation = app.load_generation;
+        let story = |id: i64, score: i64| Story {
+            score,
+            ..Story::fixture(id)
+        };
+        for (id, score) in [(1, 10), (2, 20)] {
+            app.apply_load_result(LoadResult::Story { generation, story: story(id, score) });
+        }
+        app.apply_load_result(LoadResult::Done {
+            generation,
+            end: 2,
+            failures: 0,
+        });
+        app.selected_index = 1;
+        assert_eq!(app.selected_story().map(|s| s.id), Some(1));
+
+        // Higher-scored stories from the next batch sort in above the selection.
+        app.state = AppState::LoadingMore;
+        for (id, score) in [(3, 30), (4, 40)] {
+            app.apply_load_result(LoadResult::Story { generation, story: story(id, score) });
+        }
+        assert_eq!(app.selected_story().map(|s| s.id), Some(1));
+        assert_eq!(app.selected_index, 3);
+    }
+
+    #[test]
+    fn warm_up_waits_for_the_first_load_and_skips_the_current_feed() {
+        let mut app = app_with_stories(0);
+        app.story_type = StoryType::New;
+        app.state = AppState::Loading;
+        assert!(app.due_for_warm_up().is_none());
+
+        app.state = AppState::Ready;
+        let feeds = app.due_for_warm_up().unwrap();
+        assert_eq!(feeds, [StoryType::Top, StoryType::Best, StoryType::Show, StoryType::Ask]);
+
+        app.ids_warmed_at = Some(Instant::now());
+        assert!(app.due_for_warm_up().is_none());
+        app.apply_load_result(LoadResult::WarmIds {
+            story_type: StoryType::Best,
+            ids: vec![3, 1, 2],
+        });
+        assert_eq!(app.take_warm_ids(StoryType::Best), Some(vec![3, 1, 2]));
+        // Used once, so a refresh fetches the feed again.
+        assert_eq!(app.take_warm_ids(StoryType::Best), None);
+
+        app.id_cache.insert(StoryType::Top, vec![1]);
+        app.ids_warmed_at = Instant::now().checked_sub(WARM_IDS_MAX_AGE);
+        assert_eq!(app.take_warm_ids(StoryType::Top), None);
+    }
+
+    #[test]
+    fn out_of_order_arrivals_keep_the_remembered_position() {
+        for (remembered, expected) in [(0, 1), (2, 3)] {
+            let mut app = app_with_stories(0);
+            app.positions.insert(app.story_type, (remembered, 0));
+            app.start_list((1..=5).collect(), 5);
+            app.state = AppState::Loading;
+            let generation = app.load_generation;
+            for id in [4, 1, 2, 3, 5] {
+                let story = Story::fixture(id);
+                app.apply_load_result(LoadResult::Story { generation, story });
+            }
+            app.apply_load_result(LoadResult::Done {
+                generation,
+                end: 5,
+                failures: 0,
+            });
+            assert_eq!(app.selected_index, remembered);
+            assert_eq!(app.selected_story().map(|s| s.id), Some(expected));
+        }
+    }
+
+    #[test]
+    fn digits_type_a_rank_in_split_layout() {
+        let mut app = app_with_stories(20);
+        app.layout_mode = LayoutMode::Split;
+        for digit in ['1', '2'] {
+            let typed = std::mem::take(&mut app.number_buffer);
+            assert_eq!(app.press_digit(typed, digit), None);
+        }
+        assert_eq!(app.number_buffer, "12");
+
+        app.number_buffer.clear();
+        app.show_details = true;
+        assert_eq!(app.press_digit(String::new(), '3'), Some(3));
+        assert_eq!(app.number_buffer, "");
+        assert_eq!(app.press_digit(String::new(), '0'), None);
+        let typed = std::mem::take(&mut app.number_buffer);
+        assert_eq!(app.press_digit(typed, '3'), None);
+        assert_eq!(app.number_buffer, "03");
+    }
+
+    #[test]
+    fn escape_with_a_rank_typed_only_drops_the_rank() {
+        let mut app = app_with_stories(3);
+        app.stories[0].url = Some("https://example.com/a".to_string());
+        app.filter_by_domain();
+        app.state = AppState::LoadingMore;
+
+        app.escape(true);
+        assert!(app.domain_filter.is_some());
+        assert!(app.is_loading());
+
+        app.escape(false);
+        assert!(!app.is_loading());
+        assert!(app.domain_filter.is_some());
+        app.escape(false);
+        assert!(app.domain_filter.is_none());
+    }
+
+    #[tokio::test]
+    async fn cancelling_a_category_switch_goes_back_to_the_old_list() {
+        let mut app = app_with_stories(3);
+        app.story_type = StoryType::Top;
+        app.list_type = StoryType::Top;
+        app.all_story_ids = vec![1, 2, 3];
+        app.loaded_count = 3;
+        app.selected_index = 2;
+
+        app.next_story_type();
+        app.load_stories();
+        assert!(matches!(app.state, AppState::Loading));
+        assert!(app.stories.is_empty());
+
+        app.cancel_load();
+        assert_eq!(app.story_type, StoryType::Top);
+        assert_eq!(app.stories.len(), 3);
+        assert_eq!(app.loaded_count, 3);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(3));
+        assert!(matches!(app.state, AppState::Ready));
+    }
+
+    #[test]
+    fn saved_shows_bookmarks_that_no_longer_load() {
+        let mut app = app_with_stories(0);
+        app.story_type = StoryType::Saved;
+        app.start_list(vec![1, 2, 3, 4], 4);
+        let generation = app.load_generation;
+        for id in [1, 4] {
+            let story = Story::fixture(id);
+            app.apply_load_result(LoadResult::Story { generation, story });
+        }
+        // Story 2 came back null and story 3 timed out.
+        let story = Story::placeholder(2);
+        app.apply_load_result(LoadResult::Story { generation, story });
+        app.apply_load_result(LoadResult::Done {
+            generation,
+            end: 4,
+            failures: 1,
+        });
+
+        let titles: Vec<String> = app.stories.iter().map(Story::display_title).collect();
+        assert_eq!(titles, ["Story 1", "[deleted #2]", "[couldn't load #3]", "Story 4"]);
+        assert_eq!(app.visible_count(), 4);
+    }
+
+    #[test]
+    fn hide_untitled_drops_stories_without_a_title() {
+        let mut app = app_with_stories(3);
+        app.stories[1].title = None;
+        app.stories[2].title = Some("  ".to_string());
+        assert_eq!(app.visible_count(), 3);
+        assert_eq!(app.stories[2].display_title(), "[untitled #3]");
+
+        app.hide_untitled = true;
+        assert_eq!(app.visible_count(), 1);
+    }
+
+    #[test]
+    fn wrap_navigation_wraps_at_both_ends() {
+        let mut app = app_with_stories(5);
+        app.viewport_height = 2;
+        app.prev_story();
+        assert_eq!(app.selected_index, 0);
+
+        app.wrap_navigation = true;
+        app.prev_story();
+        assert_eq!((app.selected_index, app.scroll_offset), (4, 3));
+        app.next_story();
+        assert_eq!((app.selected_index, app.scroll_offset), (0, 0));
+
+        app.wrap_navigation = false;
+        app.select(4);
+        app.next_story();
+        assert_eq!(app.selected_index, 4);
+    }
+
+    #[test]
+    fn confirm_quit_needs_a_second_press() {
+        let mut app = ready_app();
+        app.confirm_quit = false;
+        assert!(app.should_quit(None));
+
+        app.confirm_quit = true;
+        assert!(!app.should_quit(None));
+        let pending = app.pending_quit.take();
+        assert!(pending.is_some());
+        assert!(app.should_quit(pending));
+    }
+
+    #[test]
+    fn hiding_read_stories_keeps_them_loaded() {
+        let mut app = app_with_stories(4);
+        app.read = [2].into_iter().collect();
+        app.select(1);
+
+        app.toggle_hide_read();
+        assert_eq!(app.visible_count(), 3);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(3));
+        assert_eq!(app.stories.len(), 4);
+
+        // Reading the selected story hides it and moves on to the next one.
+        app.update_view(|app| {
+            app.read.insert(3);
+        });
+        assert_eq!(app.visible_count(), 2);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(4));
+
+        app.toggle_hide_read();
+        assert_eq!(app.visible_count(), 4);
+        assert_eq!(app.selected_story().map(|s| s.id), Some(4));
+    }
+
+    #[test]
+    fn prev_story_type_undoes_next_story_type() {
+        let mut app = ready_app();
+        for start in [StoryType::Top, StoryType::Ask, StoryType::Saved] {
+            app.story_type = start;
+            app.next_story_type();
+            app.prev_story_type();
+            assert_eq!(app.story_type, start);
+        }
+    }
+
+    #[test]
+    fn jump_to_page_selects_the_top_of_the_page() {
+        let mut app = app_with_stories(45);
+        app.set_viewport_height(10);
+        assert_eq!(app.page(), (1, 5));
+
+        app.jump_to_page(3);
+        assert_eq!(app.selected_index, 20);
+        assert_eq!(app.scroll_offset, 20);
+        assert_eq!(app.page(), (3, 5));
+
+        app.jump_to_page(99);
+        assert_eq!(app.selected_index, 40);
+        assert_eq!(app.page(), (5, 5));
+
+        app.jump_to_page(0);
+        assert_eq!(app.selected_index, 0);
+    }
+
+    #[test]
+    fn batch_size_follows_the_category() {
+        let mut app = App::for_tests();
+        app.batch_size = 30;
+        app.category_batch_sizes = [(StoryType::Best, 50), (StoryType::New, 15)].into();
+
+        app.story_type = StoryType::Best;
+        assert_eq!(app.current_batch_size(), 50);
+        app.story_type = StoryType::New;
+        assert_eq!(app.current_batch_size(), 15);
+        app.story_type = StoryType::Top;
+        assert_eq!(app.current_batch_size(), 30);
+    }
+
+    #[test]
+    fn markdown_export_links_each_story() {
+        let mut app = app_with_stories(2);
+        app.stories[0].title = Some("Rust [2024]".to_string());
+        app.stories[0].url = Some("https://example.com/a".to_string());
+        app.stories[0].score = 7;
+
+        let markdown = render_export(&app.stories, ExportFormat::Markdown).unwrap();
+
+        assert_eq!(
+            markdown,
+            "- [Rust \\[2024\\]](<https://example.com/a>) — 7 points \
+             ([discussion](<https://news.ycombinator.com/item?id=1>))\n\
+             - [Story 2](<https://news.ycombinator.com/item?id=2>) — 0 points \
+             ([discussion](<https://news.ycombinator.com/item?id=2>))\n"
+        );
+    }
+
+    #[test]
+    fn json_export_includes_the_hn_link() {
+        let mut app = app_with_stories(2);
+        app.stories[1].title = None;
+        let json = render_export(&app.stories, ExportFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value[0]["title"], "Story 1");
+        assert_eq!(value[1]["title"], "[untitled #2]");
+        assert_eq!(value[0]["url"], serde_json::Value::Null);
+        assert_eq!(value[0]["hn_url"], "https://news.ycombinator.com/item?id=1");
+    }
+
+    #[test]
+    fn deleted_stories_are_skipped_by_navigation() {
+        let mut app = app_with_stories(3);
+        app.stories[1].deleted = Some(true);
+
+        assert_eq!(app.visible_count(), 2);
+        app.next_story();
+        assert_eq!(app.selected_story().map(|s| s.id), Some(3));
+        app.next_story();
+        assert_eq!(app.selected_story().map(|s| s.id), Some(3));
+    }
+
+    #[test]
+    fn navigation_is_a_no_op_without_stories() {
+        let mut app = ready_app();
+
+        app.next_story();
+        app.prev_story();
+        app.page_down();
+        app.page_up();
+        app.half_page_down();
+        app.half_page_up();
+        app.next_unread();
+        app.jump_to_page(2);
+        app.select_last();
+        app.select_first();
+
+        assert_eq!(app.selected_index, 0);
+        assert_eq!(app.scroll_offset, 0);
+        assert!(app.selected_story().is_none());
+    }
+
+    #[test]
+    fn search_matches_fuzzily_and_ranks_tighter_matches_first() {
+        let mut app = app_with_stories(3);
+        app.stories[0].title = Some("Running user tests".to_string());
+        app.stories[1].title = Some("Why I left Python".to_string());
+        app.stories[2].title = Some("Rust compiler internals".to_string());
+
+        app.set_search("rst".to_string());
+
+        let ids: Vec<i64> = app.filtered_indices().iter().map(|&i| app.stories[i].id).collect();
+        assert_eq!(ids, vec![3, 1]);
+    }
+
+    #[test]
+    fn select_last_is_a_no_op_when_filter_matches_nothing() {
+        let mut app = ready_app();
+        app.set_search("no such title".to_string());
+
+        app.select_last();
+
+        assert_eq!(app.selected_index, 0);
+        assert!(app.selected_story().is_none());
     }
 }
@@ -0,0 +1,94 @@
+use crate::api::{Story, StoryType};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Every story seen recently, plus the id list last fetched for each category, so
+/// categories can be shown again without the network. Persisted between sessions.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StoryCache {
+    pub stories: HashMap<i64, Story>,
+    pub ids: HashMap<StoryType, Vec<i64>>,
+}
+
+impl StoryCache {
+    pub fn insert_story(&mut self, story: &Story) {
+        self.stories.insert(story.id, story.clone());
+    }
+
+    pub fn set_ids(&mut self, story_type: StoryType, ids: Vec<i64>) {
+        self.ids.insert(story_type, ids);
+    }
+
+    /// The cached ids for a category that have a cached story, in feed order.
+    pub fn cached_ids(&self, story_type: StoryType) -> Vec<i64> {
+        self.ids
+            .get(&story_type)
+            .map(|ids| self.available(ids))
+            .unwrap_or_default()
+    }
+
+    /// The subset of `ids` that have a cached story, in the given order.
+    pub fn available(&self, ids: &[i64]) -> Vec<i64> {
+        ids.iter()
+            .copied()
+            .filter(|id| self.stories.contains_key(id))
+            .collect()
+    }
+
+    pub fn get(&self, id: i64) -> Option<&Story> {
+        self.stories.get(&id)
+    }
+
+    /// Drops stories that no cached feed or bookmark refers to any more, so the cache
+    /// doesn't grow without bound across sessions.
+    pub fn prune(&mut self, bookmarks: &[i64]) {
+        let referenced: HashSet<i64> = self
+            .ids
+            .values()
+            .flatten()
+            .chain(bookmarks)
+            .copied()
+            .collect();
+        self.stories.retain(|id, _| referenced.contains(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(id: i64) -> Story {
+        Story {
+            score: 1,
+            ..Story::fixture(id)
+        }
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let mut cache = StoryCache::default();
+        cache.insert_story(&story(1));
+        cache.set_ids(StoryType::Top, vec![1, 2]);
+
+        let json = serde_json::to_string(&cache).unwrap();
+        let restored: StoryCache = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(restored.cached_ids(StoryType::Top), vec![1]);
+        assert_eq!(restored.get(1).and_then(|s| s.title.as_deref()), Some("Story 1"));
+    }
+
+    #[test]
+    fn prune_keeps_feed_and_bookmarked_stories() {
+        let mut cache = StoryCache::default();
+        for id in 1..=3 {
+            cache.insert_story(&story(id));
+        }
+        cache.set_ids(StoryType::New, vec![1]);
+
+        cache.prune(&[3]);
+
+        let mut ids: Vec<i64> = cache.stories.keys().copied().collect();
+        ids.sort();
+        assert_eq!(ids, vec![1, 3]);
+    }
+}
@@ -0,0 +1,135 @@
+use crate::api::{self, StoryType};
+use crate::app::DEFAULT_BATCH_SIZE;
+use crate::theme::Theme;
+
+/// Upper bound for `--batch-size`, to avoid hammering the API with huge batches.
+const MAX_BATCH_SIZE: usize = 100;
+/// Upper bound for `--limit`. HN feeds never list more than 500 stories.
+pub const MAX_LIMIT: usize = 500;
+
+const USAGE: &str = "\
+Usage: hn-tui [OPTIONS]
+
+Options:
+  --batch-size N   Stories to load per batch (1-100, default 30)
+  --category NAME  Start in top, new, best, show, ask, all or saved
+  --limit N        Stories to load in the first batch (1-500, default: batch size)
+  --offline        Show cached stories only, without network access
+  --item ID        Open the item with this id instead of a category
+  --api-url URL    Use the HN API at URL, e.g. a mirror (overrides api_url in the config)
+  --debug          Show how long each load's requests took in the status bar
+  --no-mouse       Leave the mouse to the terminal, so text can be selected and copied
+  --theme NAME     Use the built-in dark or light theme (overrides theme in the config)
+  --json           Print the category's stories (or --item) as JSON and exit
+  -h, --help       Print this help";
+
+pub struct Args {
+    pub batch_size: usize,
+    pub offline: bool,
+    pub item: Option<i64>,
+    pub category: Option<StoryType>,
+    pub limit: Option<usize>,
+    pub api_url: Option<String>,
+    pub debug: bool,
+    pub no_mouse: bool,
+    /// Print stories as JSON instead of starting the TUI.
+    pub json: bool,
+    pub theme: Option<Theme>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            offline: false,
+            item: None,
+            category: None,
+            limit: None,
+            api_url: None,
+            debug: false,
+            no_mouse: false,
+            json: false,
+            theme: None,
+        }
+    }
+}
+
+impl Args {
+    /// Parses the process arguments, printing usage and exiting on invalid input.
+    pub fn from_env() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(message) => {
+                eprintln!("{}\n\n{}", message, USAGE);
+                std::process::exit(2);
+            }
+        }
+    }
+
+    fn parse(mut iter: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut args = Self::default();
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--batch-size" => {
+                    let value = iter
+                        .next()
+                        .ok_or("--batch-size requires a value")?;
+                    let n: usize = value
+                        .parse()
+                        .map_err(|_| format!("invalid batch size: {}", value))?;
+                    if n == 0 {
+                        return Err("batch size must be at least 1".to_string());
+                    }
+                    args.batch_size = n.min(MAX_BATCH_SIZE);
+                }
+                "--category" => {
+                    let value = iter.next().ok_or("--category requires a value")?;
+                    args.category = Some(value.parse()?);
+                }
+                "--limit" => {
+                    let value = iter.next().ok_or("--limit requires a value")?;
+                    let n: usize = value
+                        .parse()
+                        .map_err(|_| format!("invalid limit: {}", value))?;
+                    if n == 0 {
+                        return Err("limit must be at least 1".to_string());
+                    }
+                    args.limit = Some(n.min(MAX_LIMIT));
+                }
+                "--offline" => args.offline = true,
+                "--debug" => args.debug = true,
+                "--no-mouse" => args.no_mouse = true,
+                "--json" => args.json = true,
+                "--theme" => {
+                    let value = iter.next().ok_or("--theme requires a value")?;
+                    let theme = Theme::preset(&value)
+                        .ok_or_else(|| format!("unknown theme: {} (use dark or light)", value))?;
+                    args.theme = Some(theme);
+                }
+                "--item" => {
+                    let value = iter.next().ok_or("--item requires a value")?;
+                    let id = value
+                        .parse()
+                        .map_err(|_| format!("invalid item id: {}", value))?;
+                    args.item = Some(id);
+                }
+                "--api-url" => {
+                    let value = iter.next().ok_or("--api-url requires a value")?;
+                    api::validate_base_url(&value)?;
+                    args.api_url = Some(value);
+                }
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                other => return Err(format!("unknown argument: {}", other)),
+            }
+        }
+
+        if args.json && args.offline {
+            return Err("--json fetches from HN and can't be combined with --offline".to_string());
+        }
+        Ok(args)
+    }
+}
@@ -0,0 +1,109 @@
+use crate::api::StoryType;
+use crate::app::SortMode;
+use crate::cli::MAX_LIMIT;
+use std::str::FromStr;
+
+/// An action typed at the `:` prompt.
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    /// `:N` jumps to page N, like the line number in vim.
+    Page(usize),
+    Category(StoryType),
+    /// Reloads the category with this many stories in the first batch.
+    Limit(usize),
+    Sort(SortMode),
+    /// Searches all of HN, not just the loaded stories, for the rest of the line.
+    Search(String),
+    Open,
+    Refresh,
+    Quit,
+}
+
+const CATEGORY_USAGE: &str = "Usage: category top|new|best|show|ask|all|saved";
+const LIMIT_USAGE: &str = "Usage: limit 1-500";
+const SORT_USAGE: &str = "Usage: sort rank|score|comments|time";
+const SEARCH_USAGE: &str = "Usage: search <words>";
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut words = s.split_whitespace();
+        let Some(name) = words.next() else {
+            return Err("Empty command".to_string());
+        };
+        // The query is everything after the name, spaces and all.
+        if name.eq_ignore_ascii_case("search") {
+            let query = words.collect::<Vec<_>>().join(" ");
+            if query.is_empty() {
+                return Err(SEARCH_USAGE.to_string());
+            }
+            return Ok(Command::Search(query));
+        }
+        let argument = words.next();
+        if words.next().is_some() {
+            return Err(format!("Too many arguments: {}", s.trim()));
+        }
+
+        if let Ok(page) = name.parse() {
+            return Ok(Command::Page(page));
+        }
+        match (name.to_lowercase().as_str(), argument) {
+            ("category" | "cat", Some(arg)) => arg
+                .parse()
+                .map(Command::Category)
+                .map_err(|_| CATEGORY_USAGE.to_string()),
+            ("category" | "cat", None) => Err(CATEGORY_USAGE.to_string()),
+            ("bookmarks", None) => Ok(Command::Category(StoryType::Saved)),
+            ("limit", Some(arg)) => match arg.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(Command::Limit(n.min(MAX_LIMIT))),
+                _ => Err(LIMIT_USAGE.to_string()),
+            },
+            ("limit", None) => Err(LIMIT_USAGE.to_string()),
+            ("sort", Some(arg)) => arg
+                .parse()
+                .map(Command::Sort)
+                .map_err(|_| SORT_USAGE.to_string()),
+            ("sort", None) => Err(SORT_USAGE.to_string()),
+            ("open", None) => Ok(Command::Open),
+            ("refresh", None) => Ok(Command::Refresh),
+            ("quit" | "q", None) => Ok(Command::Quit),
+            ("open" | "refresh" | "quit" | "q" | "bookmarks", Some(_)) => {
+                Err(format!("{} takes no arguments", name))
+            }
+            _ => Err(format!("Unknown command: {}", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_commands_and_arguments() {
+        assert_eq!("category new".parse(), Ok(Command::Category(StoryType::New)));
+        assert_eq!(" limit 50 ".parse(), Ok(Command::Limit(50)));
+        assert_eq!("limit 9999".parse(), Ok(Command::Limit(MAX_LIMIT)));
+        assert_eq!("sort score".parse(), Ok(Command::Sort(SortMode::Score)));
+        assert_eq!("12".parse(), Ok(Command::Page(12)));
+        assert_eq!("open".parse(), Ok(Command::Open));
+        assert_eq!("bookmarks".parse(), Ok(Command::Category(StoryType::Saved)));
+        assert_eq!(
+            "search  rust  async ".parse(),
+            Ok(Command::Search("rust async".to_string()))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_commands_and_bad_arguments() {
+        assert_eq!(
+            "frobnicate".parse::<Command>(),
+            Err("Unknown command: frobnicate".to_string())
+        );
+        assert_eq!("limit 0".parse::<Command>(), Err(LIMIT_USAGE.to_string()));
+        assert_eq!("sort".parse::<Command>(), Err(SORT_USAGE.to_string()));
+        assert_eq!("search".parse::<Command>(), Err(SEARCH_USAGE.to_string()));
+        assert!("open now".parse::<Command>().is_err());
+    }
+}
@@ -0,0 +1,108 @@
+use crate::api::Story;
+
+/// A comment with its replies, as shown in the comment view.
+#[derive(Debug, Clone)]
+pub struct CommentNode {
+    pub comment: Story,
+    pub children: Vec<CommentNode>,
+    /// Hides the comment's text and replies behind a one-line placeholder.
+    pub collapsed: bool,
+}
+
+impl CommentNode {
+    pub fn new(comment: Story, children: Vec<CommentNode>) -> Self {
+        Self {
+            comment,
+            children,
+            collapsed: false,
+        }
+    }
+
+    /// Replies at every depth below this comment.
+    pub fn reply_count(&self) -> usize {
+        self.children.iter().map(|child| 1 + child.reply_count()).sum()
+    }
+}
+
+/// The comments on screen, in thread order, each with its depth. A collapsed comment
+/// is listed but its replies are not.
+pub fn visible(nodes: &[CommentNode]) -> Vec<(usize, &CommentNode)> {
+    fn walk<'a>(nodes: &'a [CommentNode], depth: usize, out: &mut Vec<(usize, &'a CommentNode)>) {
+        for node in nodes {
+            out.push((depth, node));
+            if !node.collapsed {
+                walk(&node.children, depth + 1, out);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(nodes, 0, &mut out);
+    out
+}
+
+/// The `index`th comment on screen, counting as `visible` does.
+pub fn visible_mut(nodes: &mut [CommentNode], index: usize) -> Option<&mut CommentNode> {
+    fn walk<'a>(nodes: &'a mut [CommentNode], remaining: &mut usize) -> Option<&'a mut CommentNode> {
+        for node in nodes {
+            if *remaining == 0 {
+                return Some(node);
+            }
+            *remaining -= 1;
+            if !node.collapsed {
+                if let Some(found) = walk(&mut node.children, remaining) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    let mut remaining = index;
+    walk(nodes, &mut remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: i64, children: Vec<CommentNode>) -> CommentNode {
+        CommentNode::new(
+            Story {
+                title: None,
+                by: Some(format!("user{}", id)),
+                r#type: "comment".to_string(),
+                text: Some(format!("Comment {}", id)),
+                ..Story::fixture(id)
+            },
+            children,
+        )
+    }
+
+    fn ids(nodes: &[CommentNode]) -> Vec<(usize, i64)> {
+        visible(nodes).iter().map(|(depth, node)| (*depth, node.comment.id)).collect()
+    }
+
+    #[test]
+    fn collapsing_hides_the_whole_subtree() {
+        let mut thread = vec![
+            comment(1, vec![comment(2, vec![comment(3, vec![])]), comment(4, vec![])]),
+            comment(5, vec![]),
+        ];
+        assert_eq!(thread[0].reply_count(), 3);
+        assert_eq!(ids(&thread), vec![(0, 1), (1, 2), (2, 3), (1, 4), (0, 5)]);
+
+        visible_mut(&mut thread, 1).unwrap().collapsed = true;
+        assert_eq!(ids(&thread), vec![(0, 1), (1, 2), (1, 4), (0, 5)]);
+        // Positions count only what is on screen, so 3 is skipped.
+        assert_eq!(visible_mut(&mut thread, 2).unwrap().comment.id, 4);
+
+        visible_mut(&mut thread, 0).unwrap().collapsed = true;
+        assert_eq!(ids(&thread), vec![(0, 1), (0, 5)]);
+        assert!(visible_mut(&mut thread, 2).is_none());
+
+        // Expanding again restores the inner collapse as it was.
+        visible_mut(&mut thread, 0).unwrap().collapsed = false;
+        assert_eq!(ids(&thread), vec![(0, 1), (1, 2), (1, 4), (0, 5)]);
+    }
+}
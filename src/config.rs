@@ -0,0 +1,230 @@
+use crate::api::{self, StoryType};
+use crate::app::ExportFormat;
+use crate::storage;
+use crate::theme::Theme;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+/// User settings loaded from `~/.config/hackernews-tui/config.toml`. Every field is
+/// optional; a missing or unreadable file behaves like an empty one, and so does a
+/// malformed one, with a warning.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Category shown on startup: top, new, best, show, ask, all or saved.
+    pub default_category: Option<String>,
+    /// Stories per batch for individual categories, keyed by category name. Others use
+    /// `--batch-size` (default 30).
+    pub batch_sizes: HashMap<String, usize>,
+    /// Upper bound on simultaneous item requests (default 10).
+    pub max_concurrent_requests: Option<usize>,
+    /// Items requested together when loading a batch (default 10). Raise it on a fast
+    /// connection to load batches sooner; lower it on a slow or metered one.
+    pub fetch_chunk_size: Option<usize>,
+    /// Load more stories when the selection is this close to the end (default 5, 0 = off).
+    pub auto_load_threshold: Option<usize>,
+    /// Domains to emphasize in the list. Subdomains match too.
+    pub highlight_domains: Vec<String>,
+    /// Domains to mute in the list. Subdomains match too.
+    pub dim_domains: Vec<String>,
+    /// Percentage of the width given to the list in the split layout (default 60).
+    pub split_ratio: Option<u16>,
+    /// Format for `e` exports: markdown (default) or json.
+    pub export_format: Option<String>,
+    /// Refresh the New feed every this many seconds while it is shown (0 or unset = off).
+    pub auto_refresh_secs: Option<u64>,
+    /// Absolute timestamps: "24h" (default), "12h", or a chrono format string such as
+    /// "%d.%m.%Y %H:%M".
+    pub time_format: Option<String>,
+    /// Fields shown after each title in the list, in order: score, author, time,
+    /// comments and domain. Unknown names are skipped.
+    pub columns: Option<Vec<String>>,
+    /// Cut list titles to this many columns with an ellipsis, keeping every story on
+    /// one row, instead of wrapping them (0 or unset = wrap).
+    pub max_title_len: Option<usize>,
+    /// Leave items without a title out of the list instead of showing a placeholder.
+    pub hide_untitled: bool,
+    /// Keep the read-next queue between sessions instead of starting empty.
+    pub persist_queue: bool,
+    /// Moving down from the last story goes to the first, and up from the first to the
+    /// last, instead of stopping at the ends.
+    pub wrap_navigation: bool,
+    /// Ask for a second `q` before quitting.
+    pub confirm_quit: bool,
+    /// Proxy URL for all requests, e.g. `http://proxy.example.com:8080`. Overrides the
+    /// `HTTPS_PROXY` / `HTTP_PROXY` environment variables.
+    pub proxy: Option<String>,
+    /// Root of the HN API, for a mirror or caching proxy (default
+    /// `https://hacker-news.firebaseio.com/v0`). Overridden by `--api-url`.
+    pub api_url: Option<String>,
+    /// Where `O` opens a story's discussion, with `{id}` standing for the item id, e.g.
+    /// an alternate HN frontend (default `https://news.ycombinator.com/item?id={id}`).
+    pub hn_item_url_template: Option<String>,
+    /// Built-in color theme: `dark` (default) or `light`. Overridden by `--theme`.
+    pub theme: Option<String>,
+}
+
+/// A field shown after each story's title in the list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Score,
+    Author,
+    Time,
+    Comments,
+    Domain,
+}
+
+impl FromStr for Column {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "score" => Ok(Column::Score),
+            "author" => Ok(Column::Author),
+            "time" => Ok(Column::Time),
+            "comments" => Ok(Column::Comments),
+            "domain" => Ok(Column::Domain),
+            other => Err(format!("unknown column: {}", other)),
+        }
+    }
+}
+
+/// The list row's fields, in display order, each at most once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListColumns(Vec<Column>);
+
+impl ListColumns {
+    pub fn iter(&self) -> impl Iterator<Item = &Column> {
+        self.0.iter()
+    }
+}
+
+impl Default for ListColumns {
+    fn default() -> Self {
+        Self(vec![
+            Column::Score,
+            Column::Author,
+            Column::Time,
+            Column::Comments,
+            Column::Domain,
+        ])
+    }
+}
+
+impl Config {
+    /// Reads config.toml, falling back to the defaults plus a warning when it doesn't parse.
+    pub fn load() -> (Self, Option<String>) {
+        let contents = storage::config_dir().and_then(|dir| fs::read_to_string(dir.join("config.toml")).ok());
+        match contents.map(|contents| Self::parse(&contents)) {
+            Some(Err(e)) => (Self::default(), Some(e)),
+            Some(Ok(config)) => (config, None),
+            None => (Self::default(), None),
+        }
+    }
+
+    fn parse(contents: &str) -> Result<Self, String> {
+        toml::from_str(contents).map_err(|e: toml::de::Error| match e.span() {
+            Some(span) => {
+                let line = contents[..span.start].matches('\n').count() + 1;
+                format!("Ignoring config.toml, line {}: {}", line, e.message())
+            }
+            None => format!("Ignoring config.toml: {}", e.message()),
+        })
+    }
+
+    /// The per-category batch sizes, skipping unknown categories and raising zero to 1.
+    pub fn category_batch_sizes(&self) -> HashMap<StoryType, usize> {
+        self.batch_sizes
+            .iter()
+            .filter_map(|(name, &size)| Some((name.parse().ok()?, size.max(1))))
+            .collect()
+    }
+
+    /// The configured list columns, or the default layout if unset or if none of the
+    /// names are recognized. Repeated names are shown once.
+    pub fn list_columns(&self) -> ListColumns {
+        let mut columns = Vec::new();
+        for column in self.columns.iter().flatten().filter_map(|name| name.parse().ok()) {
+            if !columns.contains(&column) {
+                columns.push(column);
+            }
+        }
+        if columns.is_empty() {
+            ListColumns::default()
+        } else {
+            ListColumns(columns)
+        }
+    }
+
+    /// The configured format for absolute timestamps, or an error naming a format
+    /// string chrono can't use.
+    pub fn time_format(&self) -> Result<String, String> {
+        match self.time_format.as_deref().map(str::trim) {
+            None | Some("24h") => Ok(api::DEFAULT_TIME_FORMAT.to_string()),
+            Some("12h") => Ok(api::TWELVE_HOUR_TIME_FORMAT.to_string()),
+            Some(format) if api::is_valid_time_format(format) => Ok(format.to_string()),
+            Some(format) => Err(format!("Invalid time_format {:?}, using 24h", format)),
+        }
+    }
+
+    /// The configured API base URL, or an error if it isn't a usable http(s) URL.
+    pub fn api_url(&self) -> Result<String, String> {
+        match self.api_url.as_deref().map(str::trim) {
+            None | Some("") => Ok(api::HN_API_BASE.to_string()),
+            Some(url) => api::validate_base_url(url)
+                .map(|_| url.to_string())
+                .map_err(|e| format!("Ignoring {}", e)),
+        }
+    }
+
+    /// The configured discussion URL template, or an error if it has no `{id}` or
+    /// doesn't make an http(s) URL.
+    pub fn item_url_template(&self) -> Result<String, String> {
+        match self.hn_item_url_template.as_deref().map(str::trim) {
+            None | Some("") => Ok(api::DEFAULT_ITEM_URL_TEMPLATE.to_string()),
+            Some(template) => api::validate_item_url_template(template)
+                .map(|_| template.to_string())
+                .map_err(|e| format!("Ignoring {}", e)),
+        }
+    }
+
+    /// The configured built-in theme, or an error naming one that doesn't exist.
+    pub fn theme(&self) -> Result<Theme, String> {
+        match self.theme.as_deref() {
+            None => Ok(Theme::default()),
+            Some(name) => {
+                Theme::preset(name).ok_or_else(|| format!("Unknown theme {:?}, using dark", name))
+            }
+        }
+    }
+
+    /// The configured export format, or Markdown if unset or unrecognized.
+    pub fn export_format(&self) -> ExportFormat {
+        match self.export_format.as_deref().map(str::to_lowercase).as_deref() {
+            Some("json") => ExportFormat::Json,
+            _ => ExportFormat::Markdown,
+        }
+    }
+
+    /// The configured startup category, or `Top` if unset or unrecognized.
+    pub fn default_story_type(&self) -> StoryType {
+        self.default_category
+            .as_deref()
+            .and_then(|name| name.parse().ok())
+            .unwrap_or(StoryType::Top)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_config_reports_the_line() {
+        let err = Config::parse("theme = \"light\"\nbatch_sizes = \"lots\"\n").unwrap_err();
+        assert!(err.starts_with("Ignoring config.toml, line 2: "), "{}", err);
+        assert!(Config::parse("theme = \"light\"\n").is_ok());
+    }
+}
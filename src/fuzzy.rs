@@ -0,0 +1,103 @@
+/// A fuzzy match of a query against some text.
+#[derive(Debug, PartialEq)]
+pub struct Match {
+    /// Higher is better: matches at word starts and in runs score more, gaps less.
+    pub score: i64,
+    /// Char indices into the text of each matched query character.
+    pub positions: Vec<usize>,
+}
+
+const MATCH_SCORE: i64 = 16;
+const WORD_START_BONUS: i64 = 8;
+const CONSECUTIVE_BONUS: i64 = 8;
+const GAP_PENALTY: i64 = 1;
+
+/// Matches `query` against `text` case-insensitively, requiring the query's characters
+/// to appear in order but not necessarily together, so "rst cmplr" matches "Rust
+/// compiler". Whitespace in the query is ignored. Returns `None` when there is no match
+/// or the query is blank.
+///
+/// The match is found left to right, then tightened from its end backwards so the
+/// characters sit as close together as possible.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<Match> {
+    let needle: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if needle.is_empty() {
+        return None;
+    }
+    let original: Vec<char> = text.chars().collect();
+    let haystack: Vec<char> = original
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    // Forward pass: where the earliest complete match ends.
+    let mut next = 0;
+    let mut end = None;
+    for (i, &c) in haystack.iter().enumerate() {
+        if c == needle[next] {
+            next += 1;
+            if next == needle.len() {
+                end = Some(i);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    // Backward pass from that end picks the latest position for each character.
+    let mut positions = Vec::with_capacity(needle.len());
+    let mut wanted = needle.iter().rev();
+    let mut current = wanted.next();
+    for i in (0..=end).rev() {
+        match current {
+            Some(&c) if haystack[i] == c => {
+                positions.push(i);
+                current = wanted.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    positions.reverse();
+
+    let mut score = 0;
+    for (n, &pos) in positions.iter().enumerate() {
+        score += MATCH_SCORE;
+        if pos == 0 || !original[pos - 1].is_alphanumeric() {
+            score += WORD_START_BONUS;
+        }
+        if n > 0 {
+            let gap = (pos - positions[n - 1] - 1) as i64;
+            score += if gap == 0 {
+                CONSECUTIVE_BONUS
+            } else {
+                -gap * GAP_PENALTY
+            };
+        }
+    }
+    Some(Match { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_characters_in_order_ignoring_case_and_spaces() {
+        let m = fuzzy_match("rst cmplr", "Rust compiler").unwrap();
+        assert_eq!(m.positions, vec![0, 2, 3, 5, 7, 8, 10, 12]);
+        assert!(fuzzy_match("cmplr rst", "Rust compiler").is_none());
+        assert!(fuzzy_match("  ", "Rust compiler").is_none());
+    }
+
+    #[test]
+    fn tighter_matches_score_higher() {
+        let exact = fuzzy_match("rust", "Rust in production").unwrap();
+        let spread = fuzzy_match("rust", "Running user tests").unwrap();
+        assert!(exact.score > spread.score);
+    }
+}
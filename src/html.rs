@@ -0,0 +1,169 @@
+/// Removes every tag, keeping only the text between them.
+pub fn strip_html_tags(input: &str) -> String {
+    let mut result = String::new();
+    let mut in_tag = false;
+    for c in input.chars() {
+        if c == '<' {
+            in_tag = true;
+        } else if c == '>' {
+            in_tag = false;
+        } else if !in_tag {
+            result.push(c);
+        }
+    }
+    result
+}
+
+/// Decodes the HTML entities that appear in HN text fields.
+pub fn unescape_html(input: &str) -> String {
+    input
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&#x2F;", "/")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// The unescaped `href` of an `<a>` tag.
+pub fn link_href(tag: &str) -> Option<String> {
+    let start = tag.find("href=\"")? + "href=\"".len();
+    let end = tag[start..].find('"')?;
+    Some(unescape_html(&tag[start..start + end]))
+}
+
+/// Converts HN's HTML-formatted text to plain text.
+pub fn to_plain_text(input: &str) -> String {
+    unescape_html(&strip_html_tags(input))
+}
+
+/// Elements dropped whole from article pages: code, styling and site chrome rather
+/// than the article itself. Forms stay, since ASP.NET sites wrap the whole page in one.
+const NON_CONTENT_TAGS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "nav", "header", "footer", "aside",
+];
+
+/// Elements that start a new paragraph in the reader.
+const BLOCK_TAGS: &[&str] = &[
+    "p", "div", "br", "li", "h1", "h2", "h3", "h4", "h5", "h6", "pre", "blockquote", "tr",
+    "section", "article", "figcaption", "dt", "dd",
+];
+
+/// Pulls the readable text out of a web page: scripts, styles and navigation are
+/// dropped, the `<article>` or `<main>` element is preferred when there is one, and
+/// each block element becomes a paragraph. Paragraphs are separated by blank lines.
+pub fn extract_article(page: &str) -> String {
+    let mut page = page.to_string();
+    for tag in NON_CONTENT_TAGS {
+        page = remove_elements(&page, tag);
+    }
+    let body = ["article", "main", "body"]
+        .iter()
+        .find_map(|tag| element_contents(&page, tag))
+        .unwrap_or(&page);
+
+    let mut text = String::new();
+    let mut rest = body;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        let end = rest[start..].find('>').map_or(rest.len(), |i| start + i + 1);
+        if BLOCK_TAGS.contains(&tag_name(&rest[start..end]).as_str()) {
+            text.push('\n');
+        }
+        rest = &rest[end..];
+    }
+    text.push_str(rest);
+
+    unescape_html(&text.replace("&nbsp;", " ").replace("&#160;", " "))
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// The lowercase name of a tag like `<p class="x">` or `</p>`, empty for comments and
+/// doctypes.
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+/// Byte offset of the next `<tag` opening `tag` itself (not a longer name) at or after
+/// `from`. `lower` is the page in ASCII lowercase, which keeps byte offsets the same.
+fn find_open_tag(lower: &str, tag: &str, from: usize) -> Option<usize> {
+    let needle = format!("<{}", tag);
+    let mut at = from;
+    while let Some(i) = lower[at..].find(&needle) {
+        let start = at + i;
+        let next = lower[start + needle.len()..].chars().next();
+        if next.is_none_or(|c| c == '>' || c == '/' || c.is_whitespace()) {
+            return Some(start);
+        }
+        at = start + needle.len();
+    }
+    None
+}
+
+/// Removes every `tag` element with its contents. An unclosed one runs to the end.
+fn remove_elements(page: &str, tag: &str) -> String {
+    let lower = page.to_ascii_lowercase();
+    let close = format!("</{}", tag);
+    let mut result = String::with_capacity(page.len());
+    let mut at = 0;
+    while let Some(start) = find_open_tag(&lower, tag, at) {
+        result.push_str(&page[at..start]);
+        at = lower[start..]
+            .find(&close)
+            .and_then(|i| lower[start + i..].find('>').map(|j| start + i + j + 1))
+            .unwrap_or(page.len());
+    }
+    result.push_str(&page[at..]);
+    result
+}
+
+/// What is inside the first `tag` element, if the page has one.
+fn element_contents<'a>(page: &'a str, tag: &str) -> Option<&'a str> {
+    let lower = page.to_ascii_lowercase();
+    let start = find_open_tag(&lower, tag, 0)?;
+    let inner = start + page[start..].find('>')? + 1;
+    let end = lower[inner..]
+        .rfind(&format!("</{}", tag))
+        .map_or(page.len(), |i| inner + i);
+    Some(&page[inner..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_article_keeps_paragraphs_and_drops_chrome() {
+        let page = r#"<html><head><title>T</title><style>p { color: red }</style></head>
+            <body><nav><a href="/">Home</a></nav>
+            <Article><h1>Big   news</h1><p>First &amp; foremost.</p>
+            <script>alert("<p>no</p>")</script><p>Second<br>line</p></Article>
+            <footer>Copyright</footer></body></html>"#;
+        assert_eq!(
+            extract_article(page),
+            "Big news\n\nFirst & foremost.\n\nSecond\n\nline"
+        );
+    }
+
+    #[test]
+    fn extract_article_falls_back_to_the_whole_page() {
+        assert_eq!(extract_article("<div>One</div><navbar>Two</navbar>"), "One\n\nTwo");
+    }
+
+    #[test]
+    fn extract_article_reads_pages_wrapped_in_a_form() {
+        let page = r#"<body><form method="post" action="./post.aspx" id="form1">
+            <nav>Menu</nav><div><p>The post itself.</p></div></form></body>"#;
+        assert_eq!(extract_article(page), "The post itself.");
+    }
+}
@@ -1,54 +1,322 @@
 mod api;
 mod app;
+mod cache;
+mod cli;
+mod command;
+mod comments;
+mod config;
+mod fuzzy;
+mod html;
+mod storage;
+mod theme;
 mod ui;
 
+use api::StoryType;
 use app::App;
+use command::Command;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind,
+        KeyModifiers, MouseButton, MouseEventKind,
+    },
+    cursor::Show,
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, LeaveAlternateScreen},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io::stdout;
+use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal};
+use std::io::{self, stdout, Stdout};
 use std::panic;
+use std::time::{Duration, Instant};
+
+/// How long the event loop waits for input before redrawing and checking for finished loads.
+const TICK_RATE: Duration = Duration::from_millis(100);
+/// Two clicks on the same story within this window count as a double-click.
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    panic::set_hook(Box::new(|info| {
+    let args = cli::Args::from_env();
+    if args.json {
+        // A one-line message suits scripts better than the error's debug output.
+        if let Err(e) = print_json(&args) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mouse = !args.no_mouse;
+    panic::set_hook(Box::new(move |info| {
         disable_raw_mode().ok();
-        execute!(stdout(), LeaveAlternateScreen, DisableMouseCapture).ok();
+        execute!(stdout(), LeaveAlternateScreen).ok();
+        if mouse {
+            execute!(stdout(), DisableMouseCapture).ok();
+        }
         println!("{}", info);
     }));
 
-    enable_raw_mode()?;
+    // Declared before the terminal so it is dropped after it, on every exit path.
+    let _terminal_guard = TerminalGuard::new(mouse)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout()))?;
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let runtime_guard = runtime.enter();
+
+    let mut app = new_app(&args);
+    app.offline = args.offline;
+    app.debug = args.debug;
+    if let Some(theme) = args.theme {
+        app.theme = theme::Theme::load(theme);
+    }
+    if let Some(category) = args.category {
+        app.story_type = category;
+    }
+    if let Some(limit) = args.limit {
+        app.first_batch_size = Some(limit);
+    }
+    match args.item {
+        Some(id) => {
+            app.show_details = true;
+            app.load_single_item(id);
+        }
+        None => app.load_stories(),
+    }
+
+    let result = run(&mut terminal, &mut app);
 
-    let mut stdout = stdout();
-    execute!(stdout, EnableMouseCapture)?;
+    // The cache only speeds up the next start, so a failed write isn't worth reporting.
+    // It is written atomically, so a crash mid-write keeps the previous file intact.
+    let _ = app.save_cache();
+
+    // Don't wait on in-flight requests when quitting.
+    drop(runtime_guard);
+    runtime.shutdown_background();
+    result
+}
 
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// The app as configured, with `--api-url` in place of the config's API URL.
+fn new_app(args: &cli::Args) -> App {
+    match &args.api_url {
+        Some(url) => App::with_api_url(args.batch_size, url),
+        None => App::new_with_batch_size(args.batch_size),
+    }
+}
 
-    let mut app = App::new();
+/// `--json`: prints the first `--limit` stories of the category, or just `--item`, to
+/// stdout and returns without touching the terminal. Uses the config like the TUI does.
+fn print_json(args: &cli::Args) -> Result<(), Box<dyn std::error::Error>> {
+    let app = new_app(args);
+    // Config problems the TUI would flash in the status bar.
+    if let Some(warning) = &app.status_message {
+        eprintln!("{}", warning);
+    }
+    let story_type = args.category.unwrap_or(app.story_type);
+    let limit = args.limit.unwrap_or(args.batch_size);
 
     let runtime = tokio::runtime::Runtime::new()?;
-    runtime.block_on(async {
-        app.load_stories().await;
-    });
+    let stories = runtime.block_on(async {
+        match (args.item, story_type) {
+            (Some(id), _) => app.client.get_story(id).await.map(|story| vec![story]),
+            (None, StoryType::Saved) => {
+                let ids = &app.bookmarks[..limit.min(app.bookmarks.len())];
+                app.client.get_stories_by_ids(ids).await.map(|(stories, _)| stories)
+            }
+            (None, story_type) => app.client.get_stories(story_type, Some(limit)).await,
+        }
+    })?;
+
+    serde_json::to_writer_pretty(stdout().lock(), &stories)?;
+    println!();
+    Ok(())
+}
+
+/// Puts the terminal into raw mode with mouse capture, and restores it when dropped,
+/// whether the app quits normally, returns an error or unwinds from a panic. Mouse
+/// capture is left off with `--no-mouse`, so the terminal's own selection still works.
+struct TerminalGuard {
+    mouse: bool,
+}
+
+impl TerminalGuard {
+    fn new(mouse: bool) -> io::Result<Self> {
+        enable_raw_mode()?;
+        // Constructed before enabling mouse capture so a failure there still disables
+        // raw mode on drop.
+        let guard = Self { mouse };
+        if mouse {
+            execute!(stdout(), EnableMouseCapture)?;
+        }
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        disable_raw_mode().ok();
+        execute!(stdout(), LeaveAlternateScreen, Show).ok();
+        if self.mouse {
+            execute!(stdout(), DisableMouseCapture).ok();
+        }
+    }
+}
+
+/// Runs the event loop until the user quits.
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    app: &mut App,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Set after a first `g` so a second one completes the vim-style `gg`.
+    let mut pending_g = false;
+    let mut last_click: Option<(usize, Instant)> = None;
 
     loop {
-        terminal.draw(|frame| ui::draw(frame, &app))?;
+        app.poll_loads();
+        app.fetch_comment_previews();
+        app.prefetch_comments();
+        app.fetch_poll_options();
+        app.warm_up_ids();
+        app.auto_refresh();
+        app.poll_updates();
+        app.tick();
+        let frame = terminal.draw(|frame| ui::draw(frame, app))?;
+        app.set_viewport_height(ui::content_rows(frame.area));
+        app.set_details_max_scroll(ui::details_max_scroll(app, frame.area));
+        app.set_reader_max_scroll(ui::reader_max_scroll(app, frame.area));
+
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
+
+        let event = event::read()?;
+
+        if let Event::Resize(width, height) = event {
+            let screen = Rect::new(0, 0, width, height);
+            app.set_viewport_height(ui::content_rows(screen));
+            app.set_details_max_scroll(ui::details_max_scroll(app, screen));
+            app.set_reader_max_scroll(ui::reader_max_scroll(app, screen));
+        }
+
+        if let Event::Mouse(mouse) = event {
+            match mouse.kind {
+                MouseEventKind::Down(MouseButton::Left) => {
+                    let screen = terminal.size()?;
+                    if let Some(position) = ui::story_at(app, screen, mouse.column, mouse.row) {
+                        let double_click = matches!(
+                            last_click,
+                            Some((last, at)) if last == position && at.elapsed() < DOUBLE_CLICK
+                        );
+                        app.select(position);
+                        if double_click {
+                            open_selected_story(app);
+                            last_click = None;
+                        } else {
+                            last_click = Some((position, Instant::now()));
+                        }
+                    }
+                }
+                MouseEventKind::ScrollDown if app.comments_view.is_some() => app.next_comment(),
+                MouseEventKind::ScrollUp if app.comments_view.is_some() => app.prev_comment(),
+                MouseEventKind::ScrollDown if app.reader.is_some() => app.scroll_reader_down(1),
+                MouseEventKind::ScrollUp if app.reader.is_some() => app.scroll_reader_up(1),
+                MouseEventKind::ScrollDown => app.next_story(),
+                MouseEventKind::ScrollUp => app.prev_story(),
+                _ => {}
+            }
+        }
 
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
+        if let Event::Key(key) = event {
+            if key.kind == KeyEventKind::Press && app.searching {
+                let mut query = app.search_query.clone().unwrap_or_default();
+                match key.code {
+                    KeyCode::Esc => app.clear_search(),
+                    KeyCode::Enter => app.searching = false,
+                    KeyCode::Backspace => {
+                        query.pop();
+                        app.set_search(query);
+                    }
+                    KeyCode::Char(c) => {
+                        query.push(c);
+                        app.set_search(query);
+                    }
+                    _ => {}
+                }
+            } else if key.kind == KeyEventKind::Press && app.command_buffer.is_some() {
+                let input = app.command_buffer.get_or_insert_with(String::new);
+                match key.code {
+                    KeyCode::Esc => app.command_buffer = None,
+                    KeyCode::Enter => {
+                        let input = app.command_buffer.take().unwrap_or_default();
+                        if !input.trim().is_empty() {
+                            match input.parse() {
+                                Ok(Command::Open) => open_selected_story(app),
+                                Ok(Command::Quit) => return Ok(()),
+                                Ok(command) => app.run_command(command),
+                                Err(message) => app.flash(message),
+                            }
+                        }
+                    }
+                    // Backspacing past the `:` closes the prompt, as in vim.
+                    KeyCode::Backspace if input.is_empty() => app.command_buffer = None,
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Char(c) => input.push(c),
+                    _ => {}
+                }
+            } else if key.kind == KeyEventKind::Press && app.show_help {
+                if matches!(key.code, KeyCode::Char('?') | KeyCode::Esc) {
+                    app.toggle_help();
+                }
+            } else if key.kind == KeyEventKind::Press && app.comments_view.is_some() {
                 match key.code {
-                    KeyCode::Char('q') => {
-                        disable_raw_mode()?;
-                        execute!(
-                            terminal.backend_mut(),
-                            LeaveAlternateScreen,
-                            DisableMouseCapture
-                        )?;
-                        terminal.show_cursor()?;
-                        break;
+                    KeyCode::Char('c') | KeyCode::Esc => app.close_comments(),
+                    KeyCode::Char('j') | KeyCode::Down => app.next_comment(),
+                    KeyCode::Char('k') | KeyCode::Up => app.prev_comment(),
+                    KeyCode::Enter | KeyCode::Char(' ') => app.toggle_comment(),
+                    KeyCode::Char('O') => {
+                        if let Some(url) = app.selected_story_hn_url() {
+                            let _ = open::that(url);
+                        }
+                    }
+                    _ => {}
+                }
+            } else if key.kind == KeyEventKind::Press && app.reader.is_some() {
+                let page = app.viewport_height.max(1) as u16;
+                match key.code {
+                    KeyCode::Char('R') | KeyCode::Esc => app.close_reader(),
+                    KeyCode::Char('j') | KeyCode::Down => app.scroll_reader_down(1),
+                    KeyCode::Char('k') | KeyCode::Up => app.scroll_reader_up(1),
+                    KeyCode::PageDown | KeyCode::Char(' ') => app.scroll_reader_down(page),
+                    KeyCode::PageUp => app.scroll_reader_up(page),
+                    KeyCode::Char('o') => open_selected_story(app),
+                    _ => {}
+                }
+            } else if key.kind == KeyEventKind::Press && app.user_view.is_some() {
+                match key.code {
+                    KeyCode::Char('u') | KeyCode::Esc => app.close_user_view(),
+                    _ => {}
+                }
+            } else if key.kind == KeyEventKind::Press {
+                app.clear_status();
+                let g_was_pending = std::mem::take(&mut pending_g);
+                // Like `gg`, a rank being typed is dropped by any key but a digit or Enter.
+                let number = std::mem::take(&mut app.number_buffer);
+                let quit_pending = app.pending_quit.take();
+                let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+                match key.code {
+                    KeyCode::Char('q') if app.should_quit(quit_pending) => {
+                        return Ok(());
+                    }
+                    KeyCode::Char('d') if ctrl => {
+                        app.half_page_down();
+                    }
+                    KeyCode::Char('u') if ctrl => {
+                        app.half_page_up();
+                    }
+                    KeyCode::Char('j') | KeyCode::Down if app.show_details => {
+                        app.scroll_details_down();
+                    }
+                    KeyCode::Char('k') | KeyCode::Up if app.show_details => {
+                        app.scroll_details_up();
                     }
                     KeyCode::Char('j') | KeyCode::Down => {
                         app.next_story();
@@ -56,58 +324,187 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     KeyCode::Char('k') | KeyCode::Up => {
                         app.prev_story();
                     }
+                    KeyCode::Char(' ') if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                        app.prev_story_type();
+                        app.load_stories();
+                    }
                     KeyCode::Char(' ') => {
                         app.next_story_type();
-                        let rt = tokio::runtime::Runtime::new().unwrap();
-                        rt.block_on(async {
-                            app.load_stories().await;
-                        });
+                        app.load_stories();
+                    }
+                    KeyCode::Char('B') => {
+                        app.prev_story_type();
+                        app.load_stories();
+                    }
+                    KeyCode::Char('n') => {
+                        app.next_unread();
+                    }
+                    KeyCode::Char('b') => {
+                        app.toggle_bookmark();
+                    }
+                    KeyCode::Char('t') if app.details_visible() => {
+                        app.toggle_absolute_time();
+                    }
+                    KeyCode::Char('u') if app.details_visible() => {
+                        app.load_user();
+                    }
+                    KeyCode::Char(c @ '0'..='9') => {
+                        if let Some(n) = app.press_digit(number, c) {
+                            open_story_link(app, n);
+                        }
+                    }
+                    KeyCode::Enter if !number.is_empty() => {
+                        if let Ok(rank) = number.parse() {
+                            app.jump_to_rank(rank);
+                        }
                     }
                     KeyCode::Char('d') => {
                         app.toggle_details();
                     }
+                    KeyCode::Char('v') => {
+                        app.toggle_layout();
+                    }
                     KeyCode::Char('o') => {
-                        if let Some(url) = app.selected_story_url() {
-                            let _ = open::that(url);
-                        }
+                        open_selected_story(app);
                     }
-                    KeyCode::Char('m') => {
-                        if app.can_load_more() {
-                            let rt = tokio::runtime::Runtime::new().unwrap();
-                            rt.block_on(async {
-                                app.load_more_stories().await;
+                    KeyCode::Char('e') => {
+                        app.export();
+                    }
+                    KeyCode::Char('y') => {
+                        let url = app
+                            .selected_story_url()
+                            .cloned()
+                            .or_else(|| app.selected_story_hn_url());
+                        if let Some(url) = url {
+                            app.set_status(match copy_to_clipboard(url) {
+                                Ok(()) => "Copied!".to_string(),
+                                Err(e) => format!("Clipboard unavailable: {}", e),
                             });
                         }
                     }
-                    KeyCode::Char('r') => {
-                        if matches!(app.state, app::AppState::Error(_)) {
-                            let rt = tokio::runtime::Runtime::new().unwrap();
-                            rt.block_on(async {
-                                app.load_stories().await;
-                            });
+                    KeyCode::Char('R') => {
+                        app.open_reader();
+                    }
+                    KeyCode::Char('c') => {
+                        app.open_comments();
+                    }
+                    KeyCode::Char('D') => {
+                        app.filter_by_domain();
+                    }
+                    KeyCode::Char('x') => {
+                        if let Some(id) = app.selected_story().map(|s| s.id) {
+                            app.remove_bookmark(id);
+                        }
+                    }
+                    KeyCode::Char('O') => {
+                        if let Some(url) = app.selected_story_hn_url() {
+                            let _ = open::that(url);
+                            app.mark_comments_seen();
                         }
                     }
+                    KeyCode::Char('m') if app.can_load_more() => {
+                        app.load_more_stories();
+                    }
+                    KeyCode::Char('r') | KeyCode::F(5) => {
+                        app.refresh();
+                    }
+                    KeyCode::Char('+') | KeyCode::Char('=') => {
+                        app.raise_min_score();
+                    }
+                    KeyCode::Char('-') => {
+                        app.lower_min_score();
+                    }
+                    KeyCode::Char('h') => {
+                        app.toggle_hide_read();
+                    }
+                    KeyCode::Char('w') => {
+                        app.toggle_watch_updates();
+                    }
+                    KeyCode::Char('a') => {
+                        app.enqueue();
+                    }
+                    KeyCode::Char('A') => {
+                        if let Some(url) = app.dequeue() {
+                            let _ = open::that(url);
+                        }
+                    }
+                    KeyCode::Char('p') => {
+                        app.toggle_comment_previews();
+                    }
+                    KeyCode::Char('s') => {
+                        app.cycle_sort_mode();
+                    }
+                    KeyCode::Char('?') => {
+                        app.toggle_help();
+                    }
+                    KeyCode::Char('/') => {
+                        app.searching = true;
+                    }
+                    KeyCode::Char(':') => {
+                        app.command_buffer = Some(String::new());
+                    }
+                    KeyCode::Char('S') => {
+                        app.command_buffer = Some("search ".to_string());
+                    }
+                    KeyCode::Esc => app.escape(!number.is_empty()),
                     KeyCode::PageDown => {
                         app.page_down();
                     }
                     KeyCode::PageUp => {
                         app.page_up();
                     }
+                    KeyCode::Char('g') if g_was_pending => {
+                        app.select_first();
+                    }
+                    KeyCode::Char('g') => {
+                        pending_g = true;
+                    }
+                    KeyCode::Char('G') => {
+                        app.select_last();
+                    }
                     KeyCode::Home => {
-                        app.selected_index = 0;
-                        app.scroll_offset = 0;
+                        app.select_first();
                     }
                     KeyCode::End => {
-                        if !app.stories.is_empty() {
-                            app.selected_index = app.stories.len() - 1;
-                            app.update_scroll();
-                        }
+                        app.select_last();
                     }
                     _ => {}
                 }
             }
         }
     }
+}
 
-    Ok(())
+/// Opens the selected story in the browser and marks it read. Posts without an
+/// external link (Ask/Show text posts, jobs) open their discussion instead.
+fn open_selected_story(app: &mut App) {
+    let url = app.selected_story_url().cloned();
+    let opens_discussion = url.is_none();
+    if let Some(url) = url.or_else(|| app.selected_story_hn_url()) {
+        let _ = open::that(url);
+        if opens_discussion {
+            app.mark_comments_seen();
+        }
+    }
+    if let Some(id) = app.selected_story().map(|s| s.id) {
+        app.mark_read(id);
+    }
+}
+
+/// Opens the `n`th link (1-based) from the selected story's text, if it is numbered.
+fn open_story_link(app: &App, n: usize) {
+    if n == 0 || n > ui::MAX_NUMBERED_LINKS {
+        return;
+    }
+    let links = app
+        .selected_story()
+        .map(|s| s.extracted_links())
+        .unwrap_or_default();
+    if let Some(link) = links.get(n - 1) {
+        let _ = open::that(link);
+    }
+}
+
+fn copy_to_clipboard(text: String) -> Result<(), arboard::Error> {
+    arboard::Clipboard::new()?.set_text(text)
 }
@@ -0,0 +1,102 @@
+use crate::cache::StoryCache;
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const APP_DIR: &str = "hackernews-tui";
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".config").join(APP_DIR))
+}
+
+/// Reads a JSON file, falling back to the default value if it is missing or corrupt.
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Writes a JSON file, creating parent directories as needed. The data is written to a
+/// temporary file first and renamed into place so a crash never leaves a half-written file.
+pub fn save_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let contents = serde_json::to_string_pretty(value)?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, contents)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Where regenerable data lives, separate from settings and bookmarks.
+pub fn cache_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".cache").join(APP_DIR))
+}
+
+fn data_path(file_name: &str) -> Result<PathBuf> {
+    config_dir()
+        .map(|dir| dir.join(file_name))
+        .ok_or_else(|| anyhow::anyhow!("could not determine home directory"))
+}
+
+pub fn load_bookmarks() -> Vec<i64> {
+    data_path("bookmarks.json")
+        .map(|path| load_json(&path))
+        .unwrap_or_default()
+}
+
+pub fn save_bookmarks(bookmarks: &[i64]) -> Result<()> {
+    save_json(&data_path("bookmarks.json")?, bookmarks)
+}
+
+pub fn load_queue() -> Vec<i64> {
+    data_path("queue.json")
+        .map(|path| load_json(&path))
+        .unwrap_or_default()
+}
+
+pub fn save_queue(queue: &[i64]) -> Result<()> {
+    save_json(&data_path("queue.json")?, queue)
+}
+
+/// Comment count of each story as of the last time its discussion was opened.
+pub fn load_comment_snapshot() -> HashMap<i64, i64> {
+    data_path("comment_snapshot.json")
+        .map(|path| load_json(&path))
+        .unwrap_or_default()
+}
+
+pub fn save_comment_snapshot(snapshot: &HashMap<i64, i64>) -> Result<()> {
+    save_json(&data_path("comment_snapshot.json")?, snapshot)
+}
+
+pub fn load_read_stories() -> HashSet<i64> {
+    data_path("read.json")
+        .map(|path| load_json(&path))
+        .unwrap_or_default()
+}
+
+pub fn save_read_stories(read: &HashSet<i64>) -> Result<()> {
+    save_json(&data_path("read.json")?, read)
+}
+
+/// Loads the story cache. A cache written by an incompatible version fails to parse and
+/// is treated as empty.
+pub fn load_story_cache() -> StoryCache {
+    cache_dir()
+        .map(|dir| load_json(&dir.join("stories.json")))
+        .unwrap_or_default()
+}
+
+pub fn save_story_cache(cache: &StoryCache) -> Result<()> {
+    let dir = cache_dir().ok_or_else(|| anyhow::anyhow!("could not determine home directory"))?;
+    save_json(&dir.join("stories.json"), cache)
+}
@@ -0,0 +1,149 @@
+use crate::storage;
+use ratatui::style::Color;
+use serde::Deserialize;
+use std::fs;
+use std::str::FromStr;
+
+/// Colors used throughout the UI. Starts from a built-in preset, `dark` unless the
+/// config or `--theme` picks `light`, and takes any entry set in
+/// `~/.config/hackernews-tui/theme.toml` on top; unparseable entries keep the preset's.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub header: Color,
+    pub selected: Color,
+    pub link: Color,
+    pub meta: Color,
+    pub error: Color,
+    /// Titles, values and borders.
+    pub text: Color,
+    /// Read stories, hints and other secondary text.
+    pub muted: Color,
+    /// Background of the selected story or comment.
+    pub highlight: Color,
+    /// Points, karma, growth since the last refresh and stories under an hour old.
+    pub positive: Color,
+    /// Domains and badges such as `[updated]` and new comment counts.
+    pub accent: Color,
+    /// The job marker, set apart from the other kinds of post.
+    pub special: Color,
+}
+
+/// The theme file as written by the user. Values are either named colors (`"yellow"`,
+/// `"darkgray"`) or hex strings (`"#ff6600"`).
+#[derive(Debug, Default, Deserialize)]
+struct ThemeFile {
+    header: Option<String>,
+    selected: Option<String>,
+    link: Option<String>,
+    meta: Option<String>,
+    error: Option<String>,
+    text: Option<String>,
+    muted: Option<String>,
+    highlight: Option<String>,
+    positive: Option<String>,
+    accent: Option<String>,
+    special: Option<String>,
+}
+
+impl Theme {
+    /// `preset` with the user's theme file applied on top.
+    pub fn load(preset: Self) -> Self {
+        let file = storage::config_dir()
+            .and_then(|dir| fs::read_to_string(dir.join("theme.toml")).ok())
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self::from_file(file, preset)
+    }
+
+    /// The built-in theme called `name`: `dark` (the default) or `light`, for terminals
+    /// with a light background.
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "dark" => Some(Self::default()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    /// Dark text on the terminal's light background. Yellow, cyan, bright green and
+    /// light gray, which the dark theme uses, are hard to read there.
+    fn light() -> Self {
+        Self {
+            header: Color::Magenta,
+            selected: Color::Green,
+            link: Color::Blue,
+            meta: Color::DarkGray,
+            error: Color::Red,
+            text: Color::Black,
+            muted: Color::DarkGray,
+            highlight: Color::Gray,
+            positive: Color::Rgb(0x1a, 0x7f, 0x37),
+            accent: Color::Rgb(0x00, 0x5f, 0x87),
+            special: Color::Rgb(0x8b, 0x1f, 0x8b),
+        }
+    }
+
+    fn from_file(file: ThemeFile, base: Self) -> Self {
+        Self {
+            header: parse_color(file.header, base.header),
+            selected: parse_color(file.selected, base.selected),
+            link: parse_color(file.link, base.link),
+            meta: parse_color(file.meta, base.meta),
+            error: parse_color(file.error, base.error),
+            text: parse_color(file.text, base.text),
+            muted: parse_color(file.muted, base.muted),
+            highlight: parse_color(file.highlight, base.highlight),
+            positive: parse_color(file.positive, base.positive),
+            accent: parse_color(file.accent, base.accent),
+            special: parse_color(file.special, base.special),
+        }
+    }
+}
+
+fn parse_color(value: Option<String>, fallback: Color) -> Color {
+    value
+        .and_then(|v| Color::from_str(v.trim()).ok())
+        .unwrap_or(fallback)
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            header: Color::Yellow,
+            selected: Color::Green,
+            link: Color::Blue,
+            meta: Color::Gray,
+            error: Color::Red,
+            text: Color::White,
+            muted: Color::DarkGray,
+            highlight: Color::DarkGray,
+            positive: Color::Green,
+            accent: Color::Cyan,
+            special: Color::Magenta,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn theme_file_entries_apply_on_top_of_the_preset() {
+        let light = Theme::preset(" Light ").unwrap();
+        assert_eq!(light.text, Color::Black);
+        assert_ne!(light.accent, Theme::default().accent);
+        assert_eq!(Theme::preset("dark"), Some(Theme::default()));
+        assert_eq!(Theme::preset("solarized"), None);
+
+        let file = ThemeFile {
+            header: Some("#ff6600".to_string()),
+            highlight: Some("not a color".to_string()),
+            ..ThemeFile::default()
+        };
+        let theme = Theme::from_file(file, light);
+        assert_eq!(theme.header, Color::Rgb(0xff, 0x66, 0x00));
+        assert_eq!(theme.highlight, light.highlight);
+        assert_eq!(theme.text, Color::Black);
+    }
+}
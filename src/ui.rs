@@ -1,40 +1,179 @@
-use crate::app::{App, AppState};
+use crate::api::{self, Story, User};
+use crate::app::{App, AppState, CommentThread, CommentsView, DomainMark, ReaderView, SortMode, UserView};
+use crate::comments::{self, CommentNode};
+use crate::config::Column;
+use crate::html::{link_href, to_plain_text};
+use crate::theme::Theme;
 use ratatui::{
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style, Stylize},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation, Wrap,
+    },
     Frame,
 };
+use chrono::{Local, Utc};
+use std::rc::Rc;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-pub fn draw(frame: &mut Frame, app: &App) {
-    let chunks = Layout::default()
+/// Columns taken by the rank (`"  1. "`) and the selection/kind marker before the title.
+const TITLE_INDENT: usize = 7;
+
+/// Width in cells of the bar for a poll's leading option.
+const POLL_BAR_WIDTH: i64 = 30;
+
+/// HN wraps code blocks, including two-space-indented lines, in these tags.
+const CODE_START: &str = "<pre><code>";
+const CODE_END: &str = "</code></pre>";
+
+/// Links in a story's text that get a digit key to open them.
+pub const MAX_NUMBERED_LINKS: usize = 9;
+
+const KEY_BINDINGS: &[(&str, &str)] = &[
+    ("j / Down", "Move selection down"),
+    ("k / Up", "Move selection up"),
+    ("PageDown", "Scroll down one page"),
+    ("PageUp", "Scroll up one page"),
+    ("Ctrl-D / U", "Scroll down / up half a page"),
+    ("Home / gg", "Jump to first story"),
+    ("End / G", "Jump to last story"),
+    ("n", "Jump to next unread story"),
+    ("42 Enter", "Jump to the story ranked 42"),
+    ("Space", "Switch story category"),
+    ("B", "Switch to previous category"),
+    ("/", "Fuzzy-search loaded story titles"),
+    ("D", "Show only stories from this domain"),
+    (":", "Run a command, e.g. :sort score or :12"),
+    ("S", "Search all of HN through Algolia"),
+    ("s", "Cycle sort: rank, score, comments, time"),
+    ("p", "Toggle top comment previews"),
+    ("+ / -", "Raise / lower minimum score"),
+    ("h", "Hide / show read stories"),
+    ("w", "Watch for stories that just changed"),
+    ("Esc", "Cancel loading / clear search and domain filter / close this help"),
+    ("d", "Toggle story details"),
+    ("j / k", "Scroll the story text (in details)"),
+    ("v", "Toggle side-by-side details pane"),
+    ("u", "Show author profile (in details)"),
+    ("t", "Toggle relative / absolute time (in details)"),
+    ("1-9", "Open a numbered link (in details)"),
+    ("o", "Open story in browser"),
+    ("O", "Open HN discussion page"),
+    ("R", "Read the article in the terminal"),
+    ("c", "Show comments (Enter collapses a thread)"),
+    ("y", "Copy story link"),
+    ("e", "Export loaded stories to a file"),
+    ("b", "Bookmark / unbookmark story"),
+    ("x", "Remove story from bookmarks"),
+    ("a", "Add story to the read-next queue"),
+    ("A", "Open the next queued story"),
+    ("m", "Load more stories"),
+    ("r / F5", "Refresh the current category"),
+    ("?", "Toggle this help"),
+    ("q", "Quit"),
+];
+
+/// Splits the screen into header, content and status bar areas.
+fn screen_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
             Constraint::Min(1),
-            Constraint::Length(2),
+            Constraint::Length(3),
         ])
-        .split(frame.size());
+        .split(area)
+}
+
+/// Rows inside the content area's borders for a terminal of the given size.
+pub fn content_rows(screen: Rect) -> usize {
+    screen_layout(screen)[1].height.saturating_sub(2) as usize
+}
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let chunks = screen_layout(frame.size());
 
     draw_header(frame, app, chunks[0]);
     draw_content(frame, app, chunks[1]);
     draw_status_bar(frame, app, chunks[2]);
+
+    if app.show_help {
+        draw_help_overlay(frame, app);
+    }
+}
+
+/// Returns a rectangle of the given size centered in `area`, shrunk to fit if needed.
+fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn draw_help_overlay(frame: &mut Frame, app: &App) {
+    let lines: Vec<Line> = KEY_BINDINGS
+        .iter()
+        .map(|(key, action)| {
+            Line::from(vec![
+                Span::styled(
+                    format!("{:>10}  ", key),
+                    Style::default().fg(app.theme.header).add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(*action, Style::default().fg(app.theme.text)),
+            ])
+        })
+        .collect();
+
+    // Borders take two rows and two columns.
+    let area = centered_rect(50, KEY_BINDINGS.len() as u16 + 2, frame.size());
+    let paragraph = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Help — press ? or Esc to close")
+            .border_style(Style::default().fg(app.theme.text)),
+    );
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(paragraph, area);
 }
 
 fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
-    let title = format!("Hacker News - {} Stories", app.story_type_name());
-    let help_text = "[j/k] scroll [Space] category [d] details [o] open [m] more [q] quit";
+    let title = match (app.single_item, &app.remote_search) {
+        (Some(id), _) => format!("Hacker News - Item {}", id),
+        (None, Some(search)) => format!("Hacker News - Search: {}", search.query),
+        (None, None) => format!("Hacker News - {} Stories", app.story_type_name()),
+    };
+    let help_text = "[j/k] scroll [Space] category [/] search [b] bookmark [d] details [o/O] open/discuss [y] copy [m] more [?] help [q] quit";
 
-    let text = Line::from(vec![
-        Span::styled(title, Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)),
-        Span::raw("  "),
-        Span::styled(help_text, Style::default().fg(Color::DarkGray)),
-    ]);
+    let mut spans = vec![Span::styled(
+        title,
+        Style::default().fg(app.theme.header).add_modifier(Modifier::BOLD),
+    )];
+    if app.offline {
+        spans.push(Span::raw(" "));
+        spans.push(Span::styled(
+            " Offline ",
+            Style::default()
+                .fg(app.theme.text)
+                .bg(app.theme.error)
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    if app.auto_refreshing() {
+        spans.push(Span::styled(" ⟳ auto-refresh on", Style::default().fg(app.theme.muted)));
+    }
+    spans.push(Span::raw("  "));
+    spans.push(Span::styled(help_text, Style::default().fg(app.theme.muted)));
+    let text = Line::from(spans);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::White));
+        .border_style(Style::default().fg(app.theme.text));
 
     let paragraph = Paragraph::new(text).block(block);
     frame.render_widget(paragraph, area);
@@ -43,25 +182,55 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
 fn draw_content(frame: &mut Frame, app: &App, area: Rect) {
     match &app.state {
         AppState::Loading => {
-            let text = Text::from("Loading stories...").centered();
+            let text = Text::from(format!("{} Loading stories...", app.spinner())).centered();
             let block = Block::default().borders(Borders::ALL);
             let paragraph = Paragraph::new(text).block(block);
             frame.render_widget(paragraph, area);
         }
-        AppState::LoadingMore => {
-            let text = Text::from("Loading more stories...").centered();
+        // Stories stream in as they resolve, so the list stays visible while loading.
+        AppState::LoadingMore if app.visible_count() == 0 => {
+            let text = Text::from(format!("{} Loading more stories...", app.spinner())).centered();
             let block = Block::default().borders(Borders::ALL);
             let paragraph = Paragraph::new(text).block(block);
             frame.render_widget(paragraph, area);
         }
         AppState::Error(msg) => {
-            let text = Text::from(format!("Error: {}", msg)).centered().red();
+            let text = Text::from(format!("Error: {}", msg)).centered().fg(app.theme.error);
             let block = Block::default().borders(Borders::ALL);
             let paragraph = Paragraph::new(text).block(block);
             frame.render_widget(paragraph, area);
         }
-        AppState::Ready => {
-            if app.show_details {
+        AppState::Ready if app.visible_count() == 0 => {
+            let all_read = !app.stories.is_empty() && app.stories.iter().all(|s| app.is_read(s.id));
+            let message = if app.hide_read && all_read {
+                "Every loaded story has been read — press h to show them"
+            } else if app.stories.is_empty() && app.offline {
+                "No cached data"
+            } else if app.stories.is_empty() {
+                "No stories found"
+            } else {
+                "No stories match the current filter"
+            };
+            let text = Text::from(message).centered();
+            let block = Block::default().borders(Borders::ALL);
+            let paragraph = Paragraph::new(text).block(block);
+            frame.render_widget(paragraph, area);
+        }
+        AppState::Ready | AppState::LoadingMore => {
+            if let Some(view) = &app.comments_view {
+                draw_comments_view(frame, app, view, area);
+            } else if let Some(reader) = &app.reader {
+                draw_reader_view(frame, app, reader, area);
+            } else if app.shows_details_pane() {
+                let (list_area, details_area) = split_panes(app, area);
+                draw_story_list(frame, app, list_area);
+                match &app.user_view {
+                    Some(user_view) => draw_user_view(frame, app, user_view, details_area),
+                    None => draw_details_view(frame, app, details_area),
+                }
+            } else if let Some(user_view) = &app.user_view {
+                draw_user_view(frame, app, user_view, area);
+            } else if app.show_details {
                 draw_details_view(frame, app, area);
             } else {
                 draw_story_list(frame, app, area);
@@ -70,64 +239,113 @@ fn draw_content(frame: &mut Frame, app: &App, area: Rect) {
     }
 }
 
-fn draw_story_list(frame: &mut Frame, app: &App, area: Rect) {
-    let items: Vec<ListItem> = app
-        .stories
-        .iter()
-        .skip(app.scroll_offset)
-        .take(area.height as usize)
-        .enumerate()
-        .map(|(i, story)| {
-            let idx = app.scroll_offset + i;
-            let is_selected = idx == app.selected_index;
-            let title = story.title.clone().unwrap_or_default();
-            let has_url = story.url.is_some();
-
-            let prefix = if is_selected {
-                Span::styled("▶ ", Style::default().fg(Color::Green))
-            } else if has_url {
-                Span::styled("🔗 ", Style::default().fg(Color::Blue))
-            } else {
-                Span::styled("  ", Style::default().fg(Color::DarkGray))
-            };
+/// Splits the content area into the story list on the left and the details pane.
+fn split_panes(app: &App, area: Rect) -> (Rect, Rect) {
+    let panes = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage(app.split_ratio),
+            Constraint::Percentage(100 - app.split_ratio),
+        ])
+        .split(area);
+    (panes[0], panes[1])
+}
 
-            let title_span = if is_selected {
-                Span::styled(
-                    title.clone(),
-                    Style::default()
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD)
-                        .bg(Color::DarkGray),
-                )
-            } else {
-                Span::styled(title, Style::default().fg(Color::White))
-            };
+/// Whether the content area currently shows the story list.
+fn is_showing_list(app: &App) -> bool {
+    matches!(app.state, AppState::Ready | AppState::LoadingMore)
+        && app.visible_count() > 0
+        && !app.show_details
+        && app.reader.is_none()
+        && app.comments_view.is_none()
+        && (app.user_view.is_none() || app.shows_details_pane())
+}
 
-            let meta = format!(
-                " {} | {} | {} comments",
-                story.score,
-                story.time_ago(),
-                story.descendant.unwrap_or(0)
-            );
-            let meta_span = Span::styled(meta, Style::default().fg(Color::Gray));
+/// The stories that fit in the list `area`: the position of the first one in the
+/// filtered list, and each visible item with the number of rows it occupies.
+fn list_window<'a>(app: &App, area: Rect) -> (usize, Vec<(usize, ListItem<'a>)>) {
+    let indices = app.filtered_indices();
+    let inner_height = area.height.saturating_sub(2) as usize;
+    let title_width = (area.width.saturating_sub(2) as usize)
+        .saturating_sub(TITLE_INDENT)
+        .max(1);
 
-            let domain = format!(" ({})", story.domain());
-            let domain_span = Span::styled(domain, Style::default().fg(Color::Blue));
+    let all_items: Vec<(usize, ListItem)> = indices
+        .iter()
+        .enumerate()
+        .map(|(idx, &story_idx)| {
+            let item = build_story_item(app, story_idx, idx == app.selected_index, title_width);
+            (item.height(), item)
+        })
+        .collect();
 
-            let line = Line::from(vec![prefix, title_span, meta_span, domain_span]);
+    // `scroll_offset` counts stories, not rows. Since a wrapped story takes several rows,
+    // advance the first visible story until the selection fits on screen.
+    let mut start = app.scroll_offset.min(app.selected_index);
+    while start < app.selected_index
+        && all_items[start..=app.selected_index.min(all_items.len().saturating_sub(1))]
+            .iter()
+            .map(|(height, _)| height)
+            .sum::<usize>()
+            > inner_height
+    {
+        start += 1;
+    }
 
-            ListItem::new(line)
+    let mut used_rows = 0;
+    let window = all_items
+        .into_iter()
+        .skip(start)
+        .take_while(|(height, _)| {
+            let fits = used_rows < inner_height;
+            used_rows += height;
+            fits
         })
         .collect();
 
+    (start, window)
+}
+
+/// Maps a terminal cell to the story drawn there, as a position in the filtered list.
+/// Returns `None` when the list isn't shown or the cell is outside it.
+pub fn story_at(app: &App, screen: Rect, column: u16, row: u16) -> Option<usize> {
+    if !is_showing_list(app) {
+        return None;
+    }
+
+    let mut area = screen_layout(screen)[1];
+    if app.shows_details_pane() {
+        area = split_panes(app, area).0;
+    }
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    if column < inner.left() || column >= inner.right() || row < inner.top() || row >= inner.bottom() {
+        return None;
+    }
+
+    let (start, window) = list_window(app, area);
+    let mut top = inner.top() as usize;
+    for (i, (height, _)) in window.iter().enumerate() {
+        if (row as usize) < top + height {
+            return Some(start + i);
+        }
+        top += height;
+    }
+    None
+}
+
+fn draw_story_list(frame: &mut Frame, app: &App, area: Rect) {
+    let indices = app.filtered_indices();
+    let (_, window) = list_window(app, area);
+    let items: Vec<ListItem> = window.into_iter().map(|(_, item)| item).collect();
+
     let list = List::new(items)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .title("Stories")
-                .border_style(Style::default().fg(Color::White)),
+                .border_style(Style::default().fg(app.theme.text)),
         )
-        .highlight_style(Style::default().bg(Color::DarkGray));
+        .highlight_style(Style::default().bg(app.theme.highlight));
 
     frame.render_widget(list, area);
 
@@ -136,7 +354,7 @@ fn draw_story_list(frame: &mut Frame, app: &App, area: Rect) {
         .end_symbol(Some("▼"))
         .track_symbol(Some(" "))
         .thumb_symbol("█")
-        .style(Style::default().fg(Color::Gray));
+        .style(Style::default().fg(app.theme.meta));
 
     let scrollbar_area = Layout::default()
         .direction(Direction::Horizontal)
@@ -146,141 +364,950 @@ fn draw_story_list(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_stateful_widget(
         scrollbar,
         scrollbar_area,
-        &mut ratatui::widgets::ScrollbarState::new(app.stories.len())
+        &mut ratatui::widgets::ScrollbarState::new(indices.len())
             .position(app.selected_index)
             .viewport_content_length(area.height as usize),
     );
 }
 
+/// Builds the row for one story. Titles longer than `title_width` wrap onto continuation
+/// lines indented under the title; the metadata follows the last title line when it fits
+/// and gets its own line otherwise.
+fn build_story_item<'a>(
+    app: &App,
+    story_idx: usize,
+    is_selected: bool,
+    title_width: usize,
+) -> ListItem<'a> {
+    let story = &app.stories[story_idx];
+    let title = story.display_title();
+    let prefix = if is_selected {
+        Span::styled("▶ ", Style::default().fg(app.theme.selected))
+    } else {
+        story_kind_glyph(app, story)
+    };
+
+    let domain_mark = app.domain_mark(story);
+    let title_style = if is_selected {
+        Style::default()
+            .fg(app.theme.text)
+            .add_modifier(Modifier::BOLD)
+            .bg(app.theme.highlight)
+    } else if app.is_read(story.id) || story.is_dead() || !story.has_title() {
+        Style::default().fg(app.theme.muted)
+    } else {
+        match domain_mark {
+            Some(DomainMark::Highlight) => Style::default()
+                .fg(app.theme.header)
+                .add_modifier(Modifier::BOLD),
+            Some(DomainMark::Dim) => Style::default().fg(app.theme.muted),
+            None => Style::default().fg(app.theme.text),
+        }
+    };
+    let meta_spans = story_meta_spans(app, story);
+
+    // Rank within the whole category, so it matches HN even when filtered.
+    let rank = app.rank(story_idx);
+    let rank_span = Span::styled(format!("{:>3}. ", rank), Style::default().fg(app.theme.meta));
+
+    let indent = || Span::raw(" ".repeat(TITLE_INDENT));
+    let meta_width: usize = meta_spans.iter().map(|s| s.content.width()).sum();
+    let title_lines = match app.max_title_len {
+        // Leave room for the meta on the same row whenever the row is wide enough.
+        Some(max) => {
+            let room = title_width.checked_sub(meta_width).filter(|&room| room > 0);
+            vec![truncate_with_ellipsis(&title, max.min(room.unwrap_or(title_width)))]
+        }
+        None => wrap_text(&title, title_width),
+    };
+    let last_title_width = title_lines.last().map_or(0, |l| l.width());
+
+    let matched = app.title_match(story).map(|m| m.positions).unwrap_or_default();
+    let match_style = title_style
+        .fg(app.theme.header)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    // Char offset of each chunk in the whitespace-collapsed title the match refers to.
+    let collapsed: Vec<char> = title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+    let mut offset = 0;
+
+    let mut lines = Vec::with_capacity(title_lines.len() + 1);
+    for (i, chunk) in title_lines.into_iter().enumerate() {
+        let mut spans = if i == 0 {
+            vec![rank_span.clone(), prefix.clone()]
+        } else {
+            vec![indent()]
+        };
+        let len = chunk.chars().count();
+        spans.extend(highlight_matches(&chunk, offset, &matched, title_style, match_style));
+        // A break between words drops the space; one inside a long word doesn't.
+        offset += len;
+        if collapsed.get(offset) == Some(&' ') {
+            offset += 1;
+        }
+        lines.push(Line::from(spans));
+    }
+
+    if last_title_width + meta_width <= title_width {
+        lines.last_mut().unwrap().spans.extend(meta_spans);
+    } else {
+        let mut spans = vec![indent()];
+        spans.extend(meta_spans);
+        lines.push(Line::from(spans));
+    }
+
+    if app.show_comment_previews {
+        if let Some(comment) = &story.top_comment {
+            let preview = truncate_to_width(comment, title_width.saturating_sub(2));
+            let ellipsis = if preview.len() < comment.len() { "…" } else { "" };
+            lines.push(Line::from(vec![
+                indent(),
+                Span::styled(
+                    format!("↳ {}{}", preview, ellipsis),
+                    Style::default().fg(app.theme.muted).add_modifier(Modifier::ITALIC),
+                ),
+            ]));
+        }
+    }
+
+    ListItem::new(lines)
+}
+
+/// A two-column marker for what kind of post a story is: a link, Ask HN, Show HN, a
+/// job or a poll. Plain text posts get a blank marker.
+fn story_kind_glyph<'a>(app: &App, story: &Story) -> Span<'a> {
+    let title = story.title.as_deref().unwrap_or_default();
+    let (glyph, color) = if story.r#type == "job" {
+        ("💼", app.theme.special)
+    } else if story.is_poll() {
+        ("📊", app.theme.accent)
+    } else if title.starts_with("Ask HN") {
+        ("💬", app.theme.header)
+    } else if title.starts_with("Show HN") {
+        ("✨", app.theme.positive)
+    } else if story.url.is_some() {
+        ("🔗", app.theme.link)
+    } else {
+        ("  ", app.theme.muted)
+    };
+    Span::styled(glyph, Style::default().fg(color))
+}
+
+/// The metadata after a story's title: tags, then the configured columns.
+fn story_meta_spans<'a>(app: &App, story: &Story) -> Vec<Span<'a>> {
+    let domain_style = match app.domain_mark(story) {
+        Some(DomainMark::Highlight) => Style::default()
+            .fg(app.theme.header)
+            .add_modifier(Modifier::BOLD),
+        Some(DomainMark::Dim) => Style::default().fg(app.theme.muted),
+        None => Style::default().fg(app.theme.link),
+    };
+
+    let meta_style = Style::default().fg(app.theme.meta);
+    let delta_style = Style::default().fg(app.theme.positive);
+    let (score_delta, comments_delta) = app.delta(story.id);
+    let mut meta_spans = Vec::new();
+    if story.is_dead() {
+        meta_spans.push(Span::styled(" [flagged]", Style::default().fg(app.theme.muted)));
+    }
+    if app.is_updated(story.id) {
+        meta_spans.push(Span::styled(" [updated]", Style::default().fg(app.theme.accent)));
+    }
+    for (i, column) in app.list_columns.iter().enumerate() {
+        let separator = if i == 0 { " " } else { " | " };
+        match column {
+            Column::Score => {
+                meta_spans.push(Span::styled(format!("{}{}", separator, story.score), meta_style));
+                if score_delta > 0 {
+                    meta_spans.push(Span::styled(format!(" (+{})", score_delta), delta_style));
+                }
+            }
+            Column::Author => {
+                meta_spans.push(Span::styled(format!("{}{}", separator, story.author()), meta_style));
+            }
+            Column::Time => {
+                meta_spans.push(Span::styled(separator, meta_style));
+                meta_spans.push(Span::styled(
+                    story.time_ago(),
+                    meta_style.fg(age_color(app, Utc::now().timestamp() - story.time)),
+                ));
+            }
+            Column::Comments => {
+                meta_spans.push(Span::styled(
+                    format!("{}{}", separator, story.descendant.unwrap_or(0)),
+                    meta_style,
+                ));
+                if comments_delta > 0 {
+                    meta_spans.push(Span::styled(format!(" (+{})", comments_delta), delta_style));
+                }
+                meta_spans.push(Span::styled(" comments", meta_style));
+                if let Some(new) = app.new_comments(story) {
+                    meta_spans.push(Span::styled(
+                        format!(" (+{} new)", new),
+                        Style::default().fg(app.theme.accent).add_modifier(Modifier::BOLD),
+                    ));
+                }
+            }
+            // The domain reads as an aside to whatever precedes it, so it gets no separator.
+            Column::Domain => {
+                meta_spans.push(Span::styled(format!(" ({})", story.domain()), domain_style));
+            }
+        }
+    }
+    if app.is_bookmarked(story.id) {
+        meta_spans.push(Span::styled(" ★", Style::default().fg(app.theme.header)));
+    }
+    meta_spans
+}
+
+/// Color for a story's age in the list: positive within the hour, the terminal's own
+/// foreground within the day, and muted after that. `Reset` rather than white keeps
+/// the middle bucket readable on light backgrounds.
+fn age_color(app: &App, age_secs: i64) -> Color {
+    match age_secs {
+        ..=3599 => app.theme.positive,
+        3600..=86399 => Color::Reset,
+        _ => app.theme.meta,
+    }
+}
+
+/// Splits `chunk` into spans, styling the chars whose index plus `offset` is in
+/// `matched` with `match_style`.
+fn highlight_matches(
+    chunk: &str,
+    offset: usize,
+    matched: &[usize],
+    style: Style,
+    match_style: Style,
+) -> Vec<Span<'static>> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in chunk.chars().enumerate() {
+        let is_match = matched.contains(&(offset + i));
+        if is_match != run_matched && !run.is_empty() {
+            let style = if run_matched { match_style } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = is_match;
+        run.push(c);
+    }
+    if !run.is_empty() || spans.is_empty() {
+        spans.push(Span::styled(run, if run_matched { match_style } else { style }));
+    }
+    spans
+}
+
+/// Wraps `text` at word boundaries into lines at most `width` columns wide, measured
+/// in display width so wide CJK characters count as two. Words wider than a line,
+/// including unspaced CJK text, are split between characters.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+
+    for word in text.split_whitespace() {
+        let mut word = word;
+        let current_width = current.width();
+
+        if current_width > 0 && current_width + 1 + word.width() <= width {
+            current.push(' ');
+            current.push_str(word);
+            continue;
+        }
+        if current_width > 0 {
+            lines.push(std::mem::take(&mut current));
+        }
+        while word.width() > width && split_point(word, width) < word.len() {
+            let (head, tail) = word.split_at(split_point(word, width));
+            lines.push(head.to_string());
+            word = tail;
+        }
+        current.push_str(word);
+    }
+
+    lines.push(current);
+    lines
+}
+
+/// `text` if it fits in `width` columns, otherwise as much as fits followed by `…`.
+fn truncate_with_ellipsis(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    format!("{}…", truncate_to_width(text, width.saturating_sub(1)).trim_end())
+}
+
+/// The longest prefix of `text` at most `width` columns wide.
+fn truncate_to_width(text: &str, width: usize) -> &str {
+    let mut used = 0;
+    for (i, c) in text.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width {
+            return &text[..i];
+        }
+    }
+    text
+}
+
+/// Byte index of the end of the longest prefix of `word` at most `width` columns wide,
+/// but always past the first character so wrapping makes progress.
+fn split_point(word: &str, width: usize) -> usize {
+    let mut used = 0;
+    for (i, c) in word.char_indices() {
+        used += c.width().unwrap_or(0);
+        if used > width && i > 0 {
+            return i;
+        }
+    }
+    word.len()
+}
+
+/// Converts HN's HTML text to lines, keeping paragraphs apart. HN separates
+/// paragraphs with a bare `<p>` and otherwise keeps the author's line breaks, so list
+/// items on their own lines stay on their own lines. Italics stay italic, links show
+/// their text followed by the URL, and code blocks (`<pre><code>`) keep their
+/// indentation and get a background of their own. Code lines wider than `width` are
+/// cut short rather than wrapped. Runs of blank lines collapse to one.
+pub fn render_hn_html(input: &str, theme: &Theme, width: usize) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    let mut rest = input;
+    loop {
+        let Some(start) = rest.find(CODE_START) else {
+            push_prose(&mut lines, rest, theme);
+            break;
+        };
+        push_prose(&mut lines, &rest[..start], theme);
+        let code = &rest[start + CODE_START.len()..];
+        let end = code.find(CODE_END).unwrap_or(code.len());
+        push_code(&mut lines, &code[..end], theme, width);
+        rest = code.get(end + CODE_END.len()..).unwrap_or_default();
+    }
+    while lines.last().is_some_and(|line: &Line| line.width() == 0) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Adds a blank line unless the text so far is empty or already ends in one.
+fn push_blank(lines: &mut Vec<Line<'static>>) {
+    if lines.last().is_some_and(|line| line.width() > 0) {
+        lines.push(Line::from(""));
+    }
+}
+
+fn push_prose(lines: &mut Vec<Line<'static>>, html: &str, theme: &Theme) {
+    let link_style = Style::default().add_modifier(Modifier::UNDERLINED);
+    let mut prose: Vec<Vec<Span<'static>>> = vec![Vec::new()];
+    let mut italic = false;
+    // The href of the `<a>` being read, and its text so far.
+    let mut link: Option<(String, String)> = None;
+
+    let mut rest = html;
+    while !rest.is_empty() {
+        let start = rest.find('<').unwrap_or(rest.len());
+        let text = to_plain_text(&rest[..start]);
+        match &mut link {
+            Some((_, link_text)) => link_text.push_str(&text),
+            None => {
+                let style = if italic {
+                    Style::default().add_modifier(Modifier::ITALIC)
+                } else {
+                    Style::default()
+                };
+                push_prose_text(&mut prose, &text, style);
+            }
+        }
+        rest = &rest[start..];
+        if rest.is_empty() {
+            break;
+        }
+
+        let end = rest.find('>').map_or(rest.len(), |i| i + 1);
+        let tag = &rest[..end];
+        rest = &rest[end..];
+        match tag.to_ascii_lowercase().trim_end_matches('>').split_whitespace().next() {
+            Some("<p") => push_prose_text(&mut prose, "\n\n", Style::default()),
+            Some("<i" | "<em") => italic = true,
+            Some("</i" | "</em") => italic = false,
+            Some("<a") => link = Some((link_href(tag).unwrap_or_default(), String::new())),
+            Some("</a") => {
+                if let Some((href, text)) = link.take() {
+                    push_prose_text(&mut prose, &text, link_style);
+                    if !href.is_empty() && !link_text_is_url(&text, &href) {
+                        push_prose_text(
+                            &mut prose,
+                            &format!(" ({})", href),
+                            Style::default().fg(theme.muted),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for mut spans in prose {
+        if let Some(last) = spans.last_mut() {
+            last.content = last.content.trim_end().to_string().into();
+        }
+        if spans.iter().all(|span| span.content.trim().is_empty()) {
+            push_blank(lines);
+        } else {
+            lines.push(Line::from(spans));
+        }
+    }
+}
+
+/// Appends `text` to the last line of `prose`, starting a new line at each newline.
+fn push_prose_text(prose: &mut Vec<Vec<Span<'static>>>, text: &str, style: Style) {
+    for (i, part) in text.split('\n').enumerate() {
+        if i > 0 {
+            prose.push(Vec::new());
+        }
+        if !part.is_empty() {
+            prose.last_mut().unwrap().push(Span::styled(part.to_string(), style));
+        }
+    }
+}
+
+/// Whether a link's text already shows its URL. HN cuts long URLs short with `...`.
+fn link_text_is_url(text: &str, href: &str) -> bool {
+    let shown = text.trim().trim_end_matches("...");
+    !shown.is_empty() && href.starts_with(shown)
+}
+
+/// Code lines use non-breaking spaces, which the paragraph's wrapping doesn't trim,
+/// so indentation and alignment survive. Each line is cut to `width` with an ellipsis
+/// so the paragraph never has to wrap it.
+fn push_code(lines: &mut Vec<Line<'static>>, html: &str, theme: &Theme, width: usize) {
+    let style = Style::default().fg(theme.muted).bg(theme.highlight);
+    push_blank(lines);
+    for line in to_plain_text(html).trim_end_matches('\n').lines() {
+        let line = truncate_with_ellipsis(&line.trim_end().replace(' ', "\u{a0}"), width);
+        lines.push(Line::styled(line, style));
+    }
+    lines.push(Line::from(""));
+}
+
 fn draw_details_view(frame: &mut Frame, app: &App, area: Rect) {
     if let Some(story) = app.selected_story() {
-        let title = story.title.clone().unwrap_or_default();
-        let url = story.url.clone().unwrap_or_default();
-        let text = story.text.clone().unwrap_or_default();
-        let score = story.score.to_string();
-        let time_ago = story.time_ago();
-        let comments = story.descendant.unwrap_or(0).to_string();
-        let by = story.by.clone();
-        let domain = story.domain();
-        let story_type = story.r#type.clone();
-        let kids_count = story.kids.as_ref().map_or(0, |k| k.len());
-
-        let mut content = vec![
-            Line::from(Span::styled(
-                title,
-                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
-            )),
-            Line::from(""),
-            Line::from(vec![
-                Span::styled("Type: ", Style::default().fg(Color::Gray)),
-                Span::styled(story_type, Style::default().fg(Color::White)),
-            ]),
-            Line::from(vec![
-                Span::styled("Points: ", Style::default().fg(Color::Gray)),
-                Span::styled(score, Style::default().fg(Color::Green)),
-            ]),
-            Line::from(vec![
-                Span::styled("By: ", Style::default().fg(Color::Gray)),
-                Span::styled(by, Style::default().fg(Color::Blue)),
-            ]),
-            Line::from(vec![
-                Span::styled("Time: ", Style::default().fg(Color::Gray)),
-                Span::styled(time_ago, Style::default().fg(Color::White)),
-            ]),
-            Line::from(vec![
-                Span::styled("Comments: ", Style::default().fg(Color::Gray)),
-                Span::styled(comments, Style::default().fg(Color::White)),
-            ]),
-            Line::from(vec![
-                Span::styled("Comment IDs: ", Style::default().fg(Color::Gray)),
-                Span::styled(kids_count.to_string(), Style::default().fg(Color::White)),
-            ]),
-            Line::from(""),
-        ];
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        let paragraph = details_paragraph(app, story, inner.width).scroll((app.details_scroll, 0));
+        frame.render_widget(paragraph, area);
+    }
+}
 
-        if !url.is_empty() {
-            content.push(Line::from(vec![
-                Span::styled("URL: ", Style::default().fg(Color::Gray)),
-                Span::styled(&url, Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED)),
-            ]));
+/// How far the details of the selected story can scroll before their last line
+/// reaches the bottom of the details area, for a terminal of the given size.
+pub fn details_max_scroll(app: &App, screen: Rect) -> u16 {
+    let Some(story) = app.selected_story() else {
+        return 0;
+    };
+    let mut area = screen_layout(screen)[1];
+    if app.shows_details_pane() {
+        area = split_panes(app, area).1;
+    }
+    let inner = Block::default().borders(Borders::ALL).inner(area);
+    let lines = details_paragraph(app, story, inner.width).line_count(inner.width);
+    lines.saturating_sub(inner.height as usize).min(u16::MAX as usize) as u16
+}
+
+/// The selected story's details for an area `width` columns wide inside its border.
+fn details_paragraph<'a>(app: &App, story: &Story, width: u16) -> Paragraph<'a> {
+    let title = story.display_title();
+    let url = story.url.clone().unwrap_or_default();
+    let text = story.text.clone().unwrap_or_default();
+    let score = story.score.to_string();
+    let time = if app.show_absolute_time {
+        story.formatted_time(&app.time_format)
+    } else {
+        story.time_ago()
+    };
+    let comments = story.descendant.unwrap_or(0).to_string();
+    let by = story.author().to_string();
+    let domain = story.domain();
+    let story_type = story.r#type.clone();
+    let kids_count = story.kids.as_ref().map_or(0, |k| k.len());
+
+    // Wrapped here rather than by the paragraph so each visual line is its own span and
+    // the underline stops at the last word instead of running across the break.
+    let title_style = Style::default()
+        .fg(app.theme.header)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut content: Vec<Line> = wrap_text(&title, width as usize)
+        .into_iter()
+        .map(|line| Line::from(Span::styled(line, title_style)))
+        .collect();
+    if let Some(minutes) = story.reading_time_minutes() {
+        content.push(Line::from(Span::styled(
+            format!("~{} min read", minutes),
+            Style::default().fg(app.theme.meta).add_modifier(Modifier::ITALIC),
+        )));
+    }
+    content.extend([
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Type: ", Style::default().fg(app.theme.meta)),
+            Span::styled(story_type, Style::default().fg(app.theme.text)),
+        ]),
+        Line::from(vec![
+            Span::styled("Points: ", Style::default().fg(app.theme.meta)),
+            Span::styled(score, Style::default().fg(app.theme.positive)),
+        ]),
+        Line::from(vec![
+            Span::styled("By: ", Style::default().fg(app.theme.meta)),
+            Span::styled(by, Style::default().fg(app.theme.link)),
+        ]),
+        Line::from(vec![
+            Span::styled("Time: ", Style::default().fg(app.theme.meta)),
+            Span::styled(time, Style::default().fg(app.theme.text)),
+        ]),
+        Line::from(vec![
+            Span::styled("Comments: ", Style::default().fg(app.theme.meta)),
+            Span::styled(comments, Style::default().fg(app.theme.text)),
+        ]),
+        Line::from(vec![
+            Span::styled("Comment IDs: ", Style::default().fg(app.theme.meta)),
+            Span::styled(kids_count.to_string(), Style::default().fg(app.theme.text)),
+        ]),
+        Line::from(""),
+    ]);
+
+    if !url.is_empty() {
+        content.push(Line::from(vec![
+            Span::styled("URL: ", Style::default().fg(app.theme.meta)),
+            Span::styled(url, Style::default().fg(app.theme.link).add_modifier(Modifier::UNDERLINED)),
+        ]));
+        content.push(Line::from(vec![
+            Span::styled("Domain: ", Style::default().fg(app.theme.meta)),
+            Span::styled(domain, Style::default().fg(app.theme.accent)),
+        ]));
+        content.push(Line::from(""));
+    }
+
+    if !text.is_empty() {
+        content.push(Line::from(Span::styled(
+            "Story Text:",
+            Style::default().fg(app.theme.meta).add_modifier(Modifier::BOLD),
+        )));
+        content.push(Line::from(""));
+        content.extend(render_hn_html(&text, &app.theme, width as usize));
+        content.push(Line::from(""));
+    }
+
+    if story.is_poll() {
+        content.extend(poll_lines(app, story));
+    }
+
+    let links = story.extracted_links();
+    if !links.is_empty() {
+        content.push(Line::from(Span::styled(
+            "Links:",
+            Style::default().fg(app.theme.meta).add_modifier(Modifier::BOLD),
+        )));
+        for (i, link) in links.iter().enumerate() {
+            // Only the first nine can be opened with a single digit key.
+            let key = if i < MAX_NUMBERED_LINKS {
+                format!("[{}] ", i + 1)
+            } else {
+                "    ".to_string()
+            };
             content.push(Line::from(vec![
-                Span::styled("Domain: ", Style::default().fg(Color::Gray)),
-                Span::styled(domain, Style::default().fg(Color::Cyan)),
+                Span::styled(key, Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD)),
+                Span::styled(link.clone(), Style::default().fg(app.theme.link)),
             ]));
-            content.push(Line::from(""));
-        }
-
-        if !text.is_empty() {
-            let stripped_text = strip_html_tags(&text);
-            content.push(Line::from(Span::styled(
-                "Story Text:",
-                Style::default().fg(Color::Gray).add_modifier(Modifier::BOLD),
-            )));
-            content.push(Line::from(""));
-            for line in stripped_text.lines() {
-                if !line.trim().is_empty() {
-                    content.push(Line::from(line.to_string()));
+        }
+        content.push(Line::from(""));
+    }
+
+    let mut footer = vec![Span::styled("Press ", Style::default().fg(app.theme.muted))];
+    if !app.shows_details_pane() {
+        footer.push(Span::styled("[d]", Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD)));
+        footer.push(Span::styled(" to go back, ", Style::default().fg(app.theme.muted)));
+    }
+    footer.push(Span::styled("[u]", Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD)));
+    footer.push(Span::styled(" for the author's profile", Style::default().fg(app.theme.muted)));
+    content.push(Line::from(footer));
+
+    Paragraph::new(content)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Story Details")
+                .border_style(Style::default().fg(app.theme.text)),
+        )
+        .wrap(Wrap { trim: true })
+}
+
+/// A poll's options as a bar chart, with bars scaled to the leading option.
+fn poll_lines<'a>(app: &App, poll: &Story) -> Vec<Line<'a>> {
+    let mut lines = vec![Line::from(Span::styled(
+        "Poll:",
+        Style::default().fg(app.theme.meta).add_modifier(Modifier::BOLD),
+    ))];
+    let Some(options) = app.poll_options.get(&poll.id) else {
+        lines.push(Line::from(format!("{} Loading options...", app.spinner())));
+        lines.push(Line::from(""));
+        return lines;
+    };
+
+    let max_score = options.iter().map(|o| o.score).max().unwrap_or(0).max(1);
+    for option in options {
+        let text = to_plain_text(option.text.as_deref().unwrap_or_default());
+        let bar_width = (option.score.max(0) * POLL_BAR_WIDTH / max_score) as usize;
+        lines.push(Line::from(text));
+        lines.push(Line::from(vec![
+            Span::styled("█".repeat(bar_width), Style::default().fg(app.theme.header)),
+            Span::styled(format!(" {}", option.score), Style::default().fg(app.theme.positive)),
+        ]));
+    }
+    lines.push(Line::from(""));
+    lines
+}
+
+fn draw_user_view(frame: &mut Frame, app: &App, user_view: &UserView, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("User")
+        .border_style(Style::default().fg(app.theme.text));
+
+    let mut content = match user_view {
+        UserView::Loading(username) => vec![Line::from(format!(
+            "{} Loading {}...",
+            app.spinner(),
+            username
+        ))],
+        UserView::Error(message) => vec![Line::from(Span::styled(
+            format!("Error: {}", message),
+            Style::default().fg(app.theme.error),
+        ))],
+        UserView::Loaded(user) => user_lines(app, user, block.inner(area).width as usize),
+    };
+
+    content.push(Line::from(""));
+    content.push(Line::from(vec![
+        Span::styled("Press ", Style::default().fg(app.theme.muted)),
+        Span::styled("[u]", Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD)),
+        Span::styled(" to go back", Style::default().fg(app.theme.muted)),
+    ]));
+
+    let paragraph = Paragraph::new(content).block(block).wrap(Wrap { trim: true });
+    frame.render_widget(paragraph, area);
+}
+
+/// Comments deeper than this are indented no further, so deep threads stay readable.
+const MAX_COMMENT_INDENT: usize = 8;
+
+fn draw_comments_view(frame: &mut Frame, app: &App, view: &CommentsView, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Comments")
+        .border_style(Style::default().fg(app.theme.text));
+    let inner = block.inner(area);
+
+    let (content, selected_line) = match view {
+        CommentsView::Loading(_) => (
+            vec![Line::from(format!("{} Loading comments...", app.spinner()))],
+            0,
+        ),
+        CommentsView::Error(message) => (
+            vec![Line::from(Span::styled(
+                format!("Error: {}", message),
+                Style::default().fg(app.theme.error),
+            ))],
+            0,
+        ),
+        CommentsView::Loaded(thread) if thread.nodes.is_empty() => {
+            (vec![Line::from("No comments yet")], 0)
+        }
+        CommentsView::Loaded(thread) => comment_lines(app, thread, inner.width as usize),
+    };
+
+    // Keep the selected comment in the top third of the view as it moves down.
+    let scroll = selected_line.saturating_sub(inner.height as usize / 3);
+    let paragraph = Paragraph::new(content)
+        .block(block)
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0));
+    frame.render_widget(paragraph, area);
+}
+
+/// The thread's visible comments wrapped to `width`, and the line the selected
+/// comment starts on.
+fn comment_lines<'a>(app: &App, thread: &CommentThread, width: usize) -> (Vec<Line<'a>>, usize) {
+    let mut lines = Vec::new();
+    let mut selected_line = 0;
+    for (i, (depth, node)) in comments::visible(&thread.nodes).into_iter().enumerate() {
+        let indent = "  ".repeat(depth.min(MAX_COMMENT_INDENT));
+        let mut header_style = Style::default().fg(app.theme.link).add_modifier(Modifier::BOLD);
+        if i == thread.selected {
+            selected_line = lines.len();
+            header_style = header_style.bg(app.theme.highlight);
+        }
+        lines.push(Line::from(vec![
+            Span::raw(indent.clone()),
+            Span::styled(comment_header(node), header_style),
+        ]));
+        if node.collapsed {
+            continue;
+        }
+
+        let text_width = width.saturating_sub(indent.len()).max(1);
+        let text = node.comment.text.as_deref().unwrap_or("[deleted]");
+        for line in render_hn_html(text, &app.theme, text_width) {
+            for wrapped in wrap_line(line, text_width) {
+                if wrapped.spans.is_empty() {
+                    lines.push(wrapped);
+                    continue;
                 }
+                let mut spans = vec![Span::raw(indent.clone())];
+                spans.extend(wrapped.spans);
+                lines.push(Line::from(spans));
             }
-            content.push(Line::from(""));
         }
+        lines.push(Line::from(""));
+    }
+    (lines, selected_line)
+}
 
-        content.push(Line::from(vec![
-            Span::styled("Press ", Style::default().fg(Color::DarkGray)),
-            Span::styled("[d]", Style::default().fg(Color::White).add_modifier(Modifier::BOLD)),
-            Span::styled(" to go back", Style::default().fg(Color::DarkGray)),
-        ]));
+/// Word-wraps a styled line to `width` columns the way `wrap_text` wraps plain text,
+/// keeping each word's style. The line's own style, such as a code block's
+/// background, moves onto its spans so it survives being split.
+fn wrap_line(line: Line<'static>, width: usize) -> Vec<Line<'static>> {
+    // Each word with its style and the style of the space before it, if any.
+    let mut words: Vec<(String, Style, Option<Style>)> = Vec::new();
+    let mut space = None;
+    for span in &line.spans {
+        let style = line.style.patch(span.style);
+        for (i, word) in span.content.split(' ').enumerate() {
+            if i > 0 {
+                space = Some(style);
+            }
+            if !word.is_empty() {
+                words.push((word.to_string(), style, space.take()));
+            }
+        }
+    }
 
-        let paragraph = Paragraph::new(content)
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Story Details")
-                    .border_style(Style::default().fg(Color::White)),
-            )
-            .wrap(Wrap { trim: true });
+    let mut lines = Vec::new();
+    let mut current: Vec<Span<'static>> = Vec::new();
+    let mut current_width = 0;
+    for (word, style, space) in &words {
+        let mut word = word.as_str();
+        let gap = usize::from(space.is_some());
+        if current_width > 0 && current_width + gap + word.width() <= width {
+            if let Some(space_style) = space {
+                current.push(Span::styled(" ", *space_style));
+            }
+            current.push(Span::styled(word.to_string(), *style));
+            current_width += gap + word.width();
+            continue;
+        }
+        if current_width > 0 {
+            lines.push(Line::from(std::mem::take(&mut current)));
+        }
+        while word.width() > width && split_point(word, width) < word.len() {
+            let (head, tail) = word.split_at(split_point(word, width));
+            lines.push(Line::from(Span::styled(head.to_string(), *style)));
+            word = tail;
+        }
+        current_width = word.width();
+        current.push(Span::styled(word.to_string(), *style));
+    }
 
-        frame.render_widget(paragraph, area);
+    lines.push(Line::from(current));
+    lines
+}
+
+/// `[-] author · 3 hours ago` for an expanded comment, or a placeholder counting the
+/// hidden replies for a collapsed one.
+fn comment_header(node: &CommentNode) -> String {
+    let author = node.comment.author();
+    if !node.collapsed {
+        return format!("[-] {} · {}", author, node.comment.time_ago());
     }
+    match node.reply_count() {
+        1 => format!("[+] by {} (1 reply)", author),
+        n => format!("[+] by {} ({} replies)", author, n),
+    }
+}
+
+fn draw_reader_view(frame: &mut Frame, app: &App, reader: &ReaderView, area: Rect) {
+    let paragraph = reader_paragraph(app, reader).scroll((app.reader_scroll, 0));
+    frame.render_widget(paragraph, area);
 }
 
-fn strip_html_tags(input: &str) -> String {
-    let mut result = String::new();
-    let mut in_tag = false;
-    for c in input.chars() {
-        if c == '<' {
-            in_tag = true;
-        } else if c == '>' {
-            in_tag = false;
-        } else if !in_tag {
-            result.push(c);
+/// How far the reader can scroll before the article's last line reaches the bottom of
+/// the content area, for a terminal of the given size.
+pub fn reader_max_scroll(app: &App, screen: Rect) -> u16 {
+    let Some(reader) = &app.reader else {
+        return 0;
+    };
+    let inner = Block::default().borders(Borders::ALL).inner(screen_layout(screen)[1]);
+    let lines = reader_paragraph(app, reader).line_count(inner.width);
+    lines.saturating_sub(inner.height as usize).min(u16::MAX as usize) as u16
+}
+
+fn reader_paragraph<'a>(app: &App, reader: &ReaderView) -> Paragraph<'a> {
+    let story = app.selected_story();
+    let mut content = vec![
+        Line::from(Span::styled(
+            story.map(Story::display_title).unwrap_or_default(),
+            Style::default().fg(app.theme.header).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            story.map(|s| s.domain()).unwrap_or_default(),
+            Style::default().fg(app.theme.link),
+        )),
+        Line::from(""),
+    ];
+    match reader {
+        ReaderView::Loading(_) => {
+            content.push(Line::from(format!("{} Loading article...", app.spinner())));
+        }
+        ReaderView::Error(message) => content.push(Line::from(Span::styled(
+            format!("Error: {}", message),
+            Style::default().fg(app.theme.error),
+        ))),
+        ReaderView::Loaded(text) => {
+            for paragraph in text.split("\n\n") {
+                content.push(Line::from(paragraph.to_string()));
+                content.push(Line::from(""));
+            }
         }
     }
-    result
+
+    content.push(Line::from(vec![
+        Span::styled("Press ", Style::default().fg(app.theme.muted)),
+        Span::styled("[R]", Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD)),
+        Span::styled(" to go back, ", Style::default().fg(app.theme.muted)),
+        Span::styled("[o]", Style::default().fg(app.theme.text).add_modifier(Modifier::BOLD)),
+        Span::styled(" to open in the browser", Style::default().fg(app.theme.muted)),
+    ]));
+
+    Paragraph::new(content)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Reader")
+                .border_style(Style::default().fg(app.theme.text)),
+        )
+        .wrap(Wrap { trim: true })
+}
+
+fn user_lines<'a>(app: &App, user: &User, width: usize) -> Vec<Line<'a>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            user.id.clone(),
+            Style::default().fg(app.theme.header).add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Karma: ", Style::default().fg(app.theme.meta)),
+            Span::styled(user.karma.to_string(), Style::default().fg(app.theme.positive)),
+        ]),
+        Line::from(vec![
+            Span::styled("Created: ", Style::default().fg(app.theme.meta)),
+            Span::styled(user.created_ago(), Style::default().fg(app.theme.text)),
+        ]),
+    ];
+
+    if let Some(about) = user.about.as_deref().filter(|a| !a.is_empty()) {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "About:",
+            Style::default().fg(app.theme.meta).add_modifier(Modifier::BOLD),
+        )));
+        lines.extend(render_hn_html(about, &app.theme, width));
+    }
+
+    lines
 }
 
 fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     let (left_text, right_text) = match app.state {
+        _ if app.searching => (
+            format!("/{}", app.search_query.as_deref().unwrap_or_default()),
+            "[Enter] apply | [Esc] clear".to_string(),
+        ),
+        _ if !app.number_buffer.is_empty() => (
+            format!("Go to #{}", app.number_buffer),
+            "[Enter] go | [Esc] cancel".to_string(),
+        ),
+        _ if app.command_buffer.is_some() => (
+            format!(":{}", app.command_buffer.as_deref().unwrap_or_default()),
+            "page number, category, limit, sort, search, open, refresh, quit | [Enter] run | [Esc] cancel"
+                .to_string(),
+        ),
         AppState::Ready => {
-            let position = format!("{}/{}", app.selected_index + 1, app.stories.len());
-            let position_info = format!("Position: {}", position);
+            let count = app.visible_count();
+            let position = format!("{}/{}", (app.selected_index + 1).min(count), count);
+            let (page, pages) = app.page();
+            let mut position_info = format!("Position: {} | Page {}/{}", position, page, pages);
+            if let Some(query) = &app.search_query {
+                position_info.push_str(&format!(" | filter: {}", query));
+            }
+            if let Some(domain) = &app.domain_filter {
+                position_info.push_str(&format!(" | domain: {}", domain));
+            }
+            if app.min_score > 0 {
+                position_info.push_str(&format!(" | min score: {}", app.min_score));
+            }
+            if app.hide_read {
+                position_info.push_str(" | hiding read");
+            }
+            if app.watch_updates {
+                position_info.push_str(" | watching updates");
+            }
+            if !app.queue.is_empty() {
+                position_info.push_str(&format!(" | queue: {}", app.queue.len()));
+            }
+            if app.last_load_failures > 0 {
+                position_info.push_str(&format!(" | ({} failed to load)", app.last_load_failures));
+            }
+            if app.sort_mode != SortMode::Default {
+                position_info.push_str(&format!(" | sorted by {} (loaded only)", app.sort_mode.name()));
+            }
+            if let Some(timing) = app.last_timing.filter(|_| app.debug) {
+                position_info.push_str(&format!(" | {}", timing.summary()));
+            }
+            if let Some(updated) = last_updated(app) {
+                position_info.push_str(&format!(" | {}", updated));
+            }
             let has_link = if app.has_selected_story_url() {
-                "[o] open"
+                "[o] open [O] discuss"
             } else {
-                "[no link]"
+                "[o] discuss"
             };
             let more_info = if app.can_load_more() {
                 "[m] more"
             } else {
                 "[all loaded]"
             };
-            (position_info, format!("{} | {} | 'q' quit", has_link, more_info))
+            let right_text = match &app.status_message {
+                Some(message) => message.clone(),
+                None => format!("{} | {} | 'q' quit", has_link, more_info),
+            };
+            (position_info, right_text)
         }
-        AppState::Loading => ("Loading...".to_string(), "Press 'q' to quit".to_string()),
+        AppState::Loading => (
+            format!("{} Loading...", app.spinner()),
+            "'Esc' cancel | 'q' quit".to_string(),
+        ),
         AppState::LoadingMore => {
-            let position = format!("{}/{}", app.selected_index + 1, app.stories.len());
+            let count = app.visible_count();
+            let position = format!("{}/{}", (app.selected_index + 1).min(count), count);
             let position_info = format!("Position: {}", position);
-            (position_info, "Loading more stories...".to_string())
+            let progress = format!(
+                "{} Loaded {}/{} | 'Esc' cancel",
+                app.spinner(),
+                app.loaded_progress(),
+                app.load_total
+            );
+            (position_info, progress)
         }
         AppState::Error(_) => (
             "Error loading stories".to_string(),
@@ -289,18 +1316,219 @@ fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     };
 
     let text = Line::from(vec![
-        Span::styled(left_text, Style::default().fg(Color::White)),
+        Span::styled(
+            format!("{} ", Local::now().format("%H:%M")),
+            Style::default().fg(app.theme.meta),
+        ),
+        Span::styled(left_text, Style::default().fg(app.theme.text)),
         Span::raw(" "),
         Span::styled(
             right_text,
-            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            Style::default().fg(app.theme.muted).add_modifier(Modifier::ITALIC),
         ),
     ]);
 
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::DarkGray));
+        .border_style(Style::default().fg(app.theme.muted));
 
     let paragraph = Paragraph::new(text).block(block);
     frame.render_widget(paragraph, area);
 }
+
+/// How long ago the feed was fetched, e.g. "updated 12m ago".
+fn last_updated(app: &App) -> Option<String> {
+    let loaded = app.last_loaded?;
+    let seconds = Utc::now().signed_duration_since(loaded).num_seconds().max(0);
+    Some(format!("updated {} ago", api::humanize_duration(seconds)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn story(kind: &str, title: &str, url: Option<&str>) -> Story {
+        Story {
+            title: Some(title.to_string()),
+            url: url.map(str::to_string),
+            r#type: kind.to_string(),
+            ..Story::fixture(1)
+        }
+    }
+
+    #[test]
+    fn wrap_text_measures_display_width() {
+        assert_eq!(wrap_text("Rust in production", 10), vec!["Rust in", "production"]);
+        // Each of these characters is two columns wide.
+        assert_eq!(wrap_text("日本語のタイトル", 6), vec!["日本語", "のタイ", "トル"]);
+        assert_eq!(wrap_text("Show HN: 東京の地図", 12), vec!["Show HN:", "東京の地図"]);
+        assert!(wrap_text("漢字", 1).iter().all(|line| !line.is_empty()));
+    }
+
+    #[test]
+    fn wide_titles_push_the_meta_to_its_own_row() {
+        let mut app = App::for_tests();
+        app.list_columns = toml::from_str::<crate::config::Config>(r#"columns = ["score"]"#)
+            .unwrap()
+            .list_columns();
+        // Nine columns wide but only seven chars; the meta " 42" takes three more.
+        app.stories.push(Story {
+            id: 999_999_999,
+            score: 42,
+            ..story("story", "東京 Rust", None)
+        });
+
+        assert_eq!(build_story_item(&app, 0, false, 12).height(), 1);
+        assert_eq!(build_story_item(&app, 0, false, 11).height(), 2);
+        assert_eq!(truncate_to_width("東京 Rust", 5), "東京 ");
+    }
+
+    #[test]
+    fn max_title_len_keeps_rows_on_one_line() {
+        let mut app = App::for_tests();
+        app.list_columns = toml::from_str::<crate::config::Config>(r#"columns = ["score"]"#)
+            .unwrap()
+            .list_columns();
+        app.max_title_len = Some(10);
+        app.stories.push(Story {
+            id: 999_999_999,
+            score: 42,
+            ..story("story", "東京 is a rather long title", None)
+        });
+
+        assert_eq!(truncate_with_ellipsis("東京 is a rather long title", 10), "東京 is a…");
+        assert_eq!(truncate_with_ellipsis("Short", 10), "Short");
+        // Narrower than the limit plus the meta, so the title gives way.
+        for width in [8, 12, 40] {
+            assert_eq!(build_story_item(&app, 0, false, width).height(), 1);
+        }
+    }
+
+    #[test]
+    fn story_row_follows_the_configured_columns() {
+        let mut app = App::for_tests();
+        let config: crate::config::Config =
+            toml::from_str(r#"columns = ["domain", "bogus", "score", "domain"]"#).unwrap();
+        app.list_columns = config.list_columns();
+        app.stories.push(Story {
+            score: 42,
+            ..story("story", "A title", Some("https://example.com/post"))
+        });
+
+        let text: String = story_meta_spans(&app, &app.stories[0])
+            .iter()
+            .map(|span| span.content.to_string())
+            .collect();
+        assert_eq!(text, " (example.com) | 42");
+    }
+
+    #[test]
+    fn age_color_fades_with_age() {
+        let app = App::for_tests();
+        assert_eq!(age_color(&app, 30 * 60), app.theme.positive);
+        assert_eq!(age_color(&app, 5 * 3600), Color::Reset);
+        assert_eq!(age_color(&app, 2 * 86400), app.theme.meta);
+    }
+
+    #[test]
+    fn story_kind_glyph_tells_post_kinds_apart() {
+        let app = App::for_tests();
+        let glyph = |story: Story| story_kind_glyph(&app, &story).content.into_owned();
+
+        assert_eq!(glyph(story("story", "Ask HN: Why?", None)), "💬");
+        assert_eq!(glyph(story("story", "Show HN: A thing", Some("https://a.b"))), "✨");
+        assert_eq!(glyph(story("job", "Acme is hiring", Some("https://a.b"))), "💼");
+        assert_eq!(glyph(story("poll", "Tabs or spaces?", None)), "📊");
+        assert_eq!(glyph(story("story", "A link", Some("https://a.b"))), "🔗");
+        assert_eq!(glyph(story("story", "Some text", None)), "  ");
+    }
+
+    fn plain(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn render_hn_html_keeps_paragraphs_and_list_lines() {
+        let text = "Intro &amp; more<p>Steps:\n1. one\n2. two<p><p>- a\n- b\n";
+        assert_eq!(
+            plain(&render_hn_html(text, &Theme::default(), 80)),
+            ["Intro & more", "", "Steps:", "1. one", "2. two", "", "- a", "- b"]
+        );
+    }
+
+    #[test]
+    fn render_hn_html_styles_italics_and_links() {
+        let text = "I <i>really</i> liked <a href=\"https:&#x2F;&#x2F;example.com&#x2F;a\" \
+                    rel=\"nofollow\">this post</a>.<p>See \
+                    <a href=\"https:&#x2F;&#x2F;example.com&#x2F;a&#x2F;long\">https:&#x2F;&#x2F;example.com&#x2F;a...</a>";
+        let lines = render_hn_html(text, &Theme::default(), 80);
+        assert_eq!(
+            plain(&lines),
+            ["I really liked this post (https://example.com/a).", "", "See https://example.com/a..."]
+        );
+        assert!(lines[0].spans[1].style.add_modifier.contains(Modifier::ITALIC));
+        assert!(!lines[0].spans[2].style.add_modifier.contains(Modifier::ITALIC));
+        assert!(lines[0].spans[3].style.add_modifier.contains(Modifier::UNDERLINED));
+    }
+
+    #[test]
+    fn render_hn_html_keeps_code_indentation() {
+        let text = "Try this:<p><pre><code>  fn main() {\n      run();\n  }\n</code></pre>Done.";
+        let lines = render_hn_html(text, &Theme::default(), 80);
+        assert_eq!(
+            plain(&lines),
+            [
+                "Try this:",
+                "",
+                "\u{a0}\u{a0}fn\u{a0}main()\u{a0}{",
+                "\u{a0}\u{a0}\u{a0}\u{a0}\u{a0}\u{a0}run();",
+                "\u{a0}\u{a0}}",
+                "",
+                "Done.",
+            ]
+        );
+        assert_eq!(lines[2].style.bg, Some(Theme::default().highlight));
+        assert_eq!(lines[0].style.bg, None);
+    }
+
+    #[test]
+    fn render_hn_html_cuts_long_code_lines_short() {
+        let text = "<pre><code>let total = first + second;\nok();</code></pre>";
+        let lines = render_hn_html(text, &Theme::default(), 12);
+        assert_eq!(plain(&lines), ["let\u{a0}total\u{a0}=…", "ok();"]);
+    }
+
+    #[test]
+    fn comments_keep_code_indentation_and_wrap_prose() {
+        let comment = Story {
+            r#type: "comment".to_string(),
+            text: Some(
+                "A <i>long</i> line of prose<p><pre><code>  let x = 1;\n</code></pre>".to_string(),
+            ),
+            ..Story::fixture(2)
+        };
+        let thread = CommentThread {
+            nodes: vec![CommentNode::new(
+                Story::fixture(1),
+                vec![CommentNode::new(comment, Vec::new())],
+            )],
+            selected: 0,
+        };
+        let app = App::for_tests();
+        let (lines, _) = comment_lines(&app, &thread, 14);
+        let text = plain(&lines);
+        assert_eq!(
+            text[text.len() - 5..],
+            [
+                "  A long line",
+                "  of prose",
+                "",
+                "  \u{a0}\u{a0}let\u{a0}x\u{a0}=\u{a0}1;",
+                "",
+            ]
+        );
+    }
+}